@@ -61,16 +61,30 @@ async fn main() -> Result<()> {
 
     // Show the default path that would be used
     println!("\n4. Default database location:");
-    println!("Platform: {}", if cfg!(target_os = "windows") { "Windows" } else { "Linux/Unix" });
+    println!("Platform: {}", if cfg!(target_os = "windows") {
+        "Windows"
+    } else if cfg!(target_os = "macos") {
+        "macOS"
+    } else {
+        "Linux/Unix"
+    });
 
     // This uses internal function logic to show the path
     let expected_path = if cfg!(target_os = "windows") {
         std::env::var("USERPROFILE").map(|profile|
             format!("{}\\AppData\\Local\\BurnCloud\\data.db", profile)
         ).unwrap_or_else(|_| "Could not determine USERPROFILE".to_string())
+    } else if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|home|
+            format!("{}/Library/Application Support/BurnCloud/data.db", home.display())
+        ).unwrap_or_else(|| "Could not determine home directory".to_string())
+    } else if let Some(data_home) =
+        std::env::var_os("XDG_DATA_HOME").filter(|dir| std::path::Path::new(dir).is_absolute())
+    {
+        format!("{}/burncloud/data.db", std::path::Path::new(&data_home).display())
     } else {
         dirs::home_dir().map(|home|
-            format!("{}/.burncloud/data.db", home.display())
+            format!("{}/.local/share/burncloud/data.db", home.display())
         ).unwrap_or_else(|| "Could not determine home directory".to_string())
     };
 
@@ -1,27 +1,403 @@
-use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
-use std::path::Path;
+use futures_util::{future, stream, Stream, StreamExt, TryStreamExt};
+use sqlx::pool::PoolConnection;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePoolOptions, SqliteRow};
+use sqlx::{Column as _, Executor, Row, Sqlite, SqlitePool, Transaction};
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::HashMap;
+use std::future::Future;
+use std::os::raw::c_int;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
 use crate::error::{DatabaseError, Result};
+use crate::sql::{
+    chunk_params, normalize_sql, quote_identifier, split_statements, DEFAULT_MAX_VARIABLES, MAX_VARIABLES_CEILING,
+};
+use crate::stats::{global_open_connections, register_pool, PoolMetrics, PoolStats};
+use crate::types::{ColumnBatch, DatabaseConfig, QueryResult};
+use crate::value::{
+    decode_row, describe_params, describe_text_params, row_to_json, to_arguments, BoolStorage, DateTimeStorage,
+    SqlValue, ValueEncoding,
+};
 
 #[derive(Clone)]
 pub struct DatabaseConnection {
-    pool: SqlitePool,
+    pool: Arc<SqlitePool>,
+    metrics: Arc<PoolMetrics>,
+    // Connections opened before this instant are discarded on checkout
+    stale_before: Arc<Mutex<Option<Instant>>>,
+    // Bumped by `InterruptHandle::interrupt`; see `watch_interrupts`
+    interrupts: Arc<AtomicU64>,
+}
+
+/// Cancels statements running on a database's connections from another task,
+/// for example when a user stops a long query. Interrupted statements fail
+/// with `Connection` carrying SQLite's interrupt error.
+///
+/// Connections check for an interrupt every few thousand SQLite instructions,
+/// so very short statements run to completion. A statement started on a
+/// connection that is still checked out when `interrupt` is called is
+/// interrupted too; pooled idle connections are unaffected. Pools wrapped with
+/// `Database::from_connection` are not watched.
+#[derive(Clone)]
+pub struct InterruptHandle {
+    interrupts: Arc<AtomicU64>,
+}
+
+impl InterruptHandle {
+    pub fn interrupt(&self) {
+        self.interrupts.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Pool sizing and lifetime settings, translated directly into the matching
+/// `SqlitePoolOptions` calls. The defaults match sqlx's, apart from the pool
+/// size of 10.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolConfig {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Option<Duration>,
+    pub max_lifetime: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(30),
+            idle_timeout: Some(Duration::from_secs(10 * 60)),
+            max_lifetime: Some(Duration::from_secs(30 * 60)),
+        }
+    }
+}
+
+/// How `execute_*` and `insert` calls retry when SQLite reports the
+/// database as busy or locked; see `Database::with_retry`. Attempt `n` waits
+/// `backoff * 2^(n-1)` before the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first; 1 disables retrying.
+    pub max_attempts: u32,
+    pub backoff: Duration,
+}
+
+/// SQLite journal modes selectable through `Database::new_with_journal_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalMode {
+    Wal,
+    Delete,
+    Truncate,
+    Memory,
+}
+
+impl JournalMode {
+    fn as_pragma_value(self) -> &'static str {
+        match self {
+            JournalMode::Wal => "WAL",
+            JournalMode::Delete => "DELETE",
+            JournalMode::Truncate => "TRUNCATE",
+            JournalMode::Memory => "MEMORY",
+        }
+    }
+}
+
+// Connection options collected by the `Database` builder methods and
+// applied when the pool is created in `initialize`.
+pub(crate) type Collation = Arc<dyn Fn(&str, &str) -> CmpOrdering + Send + Sync>;
+
+pub(crate) type SlowQueryCallback = Arc<dyn Fn(&str, Duration) + Send + Sync>;
+
+#[derive(Clone)]
+pub(crate) struct SlowQuery {
+    pub(crate) threshold: Duration,
+    // Logged through `tracing` when unset
+    pub(crate) callback: Option<SlowQueryCallback>,
+}
+
+#[derive(Clone)]
+pub(crate) struct ConnectionSettings {
+    pub(crate) pool: PoolConfig,
+    pub(crate) test_before_acquire: bool,
+    pub(crate) max_variable_number: Option<u32>,
+    pub(crate) read_only: bool,
+    pub(crate) create_if_missing: bool,
+    pub(crate) journal_mode: Option<JournalMode>,
+    pub(crate) busy_timeout_ms: u32,
+    pub(crate) foreign_keys: bool,
+    pub(crate) encoding: ValueEncoding,
+    pub(crate) collations: Vec<(String, Collation)>,
+    pub(crate) log_failed_queries: bool,
+    pub(crate) retry: Option<RetryPolicy>,
+    pub(crate) slow_query: Option<SlowQuery>,
+    pub(crate) encryption_key: Option<String>,
+}
+
+impl Default for ConnectionSettings {
+    fn default() -> Self {
+        Self {
+            pool: PoolConfig::default(),
+            test_before_acquire: true,
+            max_variable_number: None,
+            read_only: false,
+            create_if_missing: true,
+            journal_mode: None,
+            busy_timeout_ms: 5000,
+            foreign_keys: true,
+            encoding: ValueEncoding::default(),
+            collations: Vec::new(),
+            log_failed_queries: false,
+            retry: None,
+            slow_query: None,
+            encryption_key: None,
+        }
+    }
+}
+
+impl ConnectionSettings {
+    // The bound-variable limit multi-bind helpers size their statements to.
+    pub(crate) fn max_variables(&self) -> usize {
+        self.max_variable_number
+            .map_or(DEFAULT_MAX_VARIABLES, |limit| (limit as usize).min(MAX_VARIABLES_CEILING))
+    }
+}
+
+// Rolls back a transaction that raw `BEGIN` left open on `conn`, so it is not
+// returned to the pool mid-transaction. If that fails, the connection is
+// closed instead.
+async fn abandon_open_transaction(mut conn: PoolConnection<Sqlite>) {
+    let in_transaction = match conn.lock_handle().await {
+        // SAFETY: the handle is locked, so no statement runs concurrently
+        Ok(mut handle) => unsafe { libsqlite3_sys::sqlite3_get_autocommit(handle.as_raw_handle().as_ptr()) == 0 },
+        Err(_) => true,
+    };
+    if in_transaction && sqlx::query("ROLLBACK").execute(&mut *conn).await.is_err() {
+        let _ = sqlx::Connection::close(conn.detach()).await;
+    }
+}
+
+// Instructions between two interrupt checks on a connection
+const INTERRUPT_CHECK_OPS: i32 = 1000;
+
+// Installs a progress handler that aborts statements once `interrupts` moves
+// past its current value. It keeps aborting until it is installed again, so
+// sqlx cannot quietly re-run the interrupted statement; `before_acquire`
+// reinstalls it when the connection is next checked out.
+async fn watch_interrupts(
+    conn: &mut SqliteConnection,
+    interrupts: &Arc<AtomicU64>,
+) -> std::result::Result<(), sqlx::Error> {
+    let interrupts = interrupts.clone();
+    let seen = interrupts.load(Ordering::SeqCst);
+    let mut handle = conn.lock_handle().await?;
+    handle.set_progress_handler(INTERRUPT_CHECK_OPS, move || interrupts.load(Ordering::SeqCst) == seen);
+    Ok(())
+}
+
+// Applies per-connection settings that have no connect-option equivalent.
+async fn configure_connection(
+    conn: &mut SqliteConnection,
+    settings: &ConnectionSettings,
+) -> std::result::Result<(), sqlx::Error> {
+    // SQLCipher needs the key before anything touches the file. PRAGMA key
+    // itself never fails, so read the schema to surface a wrong key here.
+    if let Some(key) = &settings.encryption_key {
+        sqlx::query(&format!("PRAGMA key = '{}'", key.replace('\'', "''")))
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("SELECT COUNT(*) FROM sqlite_master").execute(&mut *conn).await?;
+    }
+
+    // Set first so the remaining pragmas also wait for locks instead of failing
+    sqlx::query(&format!("PRAGMA busy_timeout = {}", settings.busy_timeout_ms))
+        .execute(&mut *conn)
+        .await?;
+
+    // Foreign keys are a per-connection setting, so every pooled connection needs it
+    sqlx::query(if settings.foreign_keys {
+        "PRAGMA foreign_keys = ON"
+    } else {
+        "PRAGMA foreign_keys = OFF"
+    })
+    .execute(&mut *conn)
+    .await?;
+
+    if let Some(mode) = settings.journal_mode {
+        sqlx::query(&format!("PRAGMA journal_mode = {}", mode.as_pragma_value()))
+            .execute(&mut *conn)
+            .await?;
+        if mode == JournalMode::Wal {
+            // NORMAL is durable in WAL mode and avoids an fsync per commit
+            sqlx::query("PRAGMA synchronous = NORMAL").execute(&mut *conn).await?;
+        }
+    }
+
+    if let Some(limit) = settings.max_variable_number {
+        let mut handle = conn.lock_handle().await?;
+        let limit = limit.min(c_int::MAX as u32) as c_int;
+        // SAFETY: the handle is locked, so no other statement uses the
+        // connection while the limit is changed.
+        unsafe {
+            libsqlite3_sys::sqlite3_limit(
+                handle.as_raw_handle().as_ptr(),
+                libsqlite3_sys::SQLITE_LIMIT_VARIABLE_NUMBER,
+                limit,
+            );
+        }
+    }
+
+    if !settings.collations.is_empty() {
+        let mut handle = conn.lock_handle().await?;
+        for (name, compare) in &settings.collations {
+            let compare = compare.clone();
+            handle.create_collation(name, move |a, b| compare(a, b))?;
+        }
+    }
+    Ok(())
 }
 
 impl DatabaseConnection {
     pub async fn new(database_url: &str) -> Result<Self> {
+        Self::connect(database_url, &ConnectionSettings::default()).await
+    }
+
+    pub async fn new_with_options(database_url: &str, max_connections: u32) -> Result<Self> {
+        let mut settings = ConnectionSettings::default();
+        settings.pool.max_connections = max_connections;
+        Self::connect(database_url, &settings).await
+    }
+
+    pub(crate) async fn connect(database_url: &str, settings: &ConnectionSettings) -> Result<Self> {
+        Self::connect_with(SqliteConnectOptions::from_str(database_url)?, settings).await
+    }
+
+    pub(crate) async fn connect_with(options: SqliteConnectOptions, settings: &ConnectionSettings) -> Result<Self> {
+        let pool_config = &settings.pool;
+        if pool_config.max_connections == 0 {
+            return Err(DatabaseError::InvalidData {
+                message: "max_connections must be at least 1".to_string(),
+            });
+        }
+        if pool_config.min_connections > pool_config.max_connections {
+            return Err(DatabaseError::InvalidData {
+                message: "min_connections cannot exceed max_connections".to_string(),
+            });
+        }
+
+        let hook_settings = settings.clone();
+        let stale_before = Arc::new(Mutex::new(None));
+        let hook_stale_before = stale_before.clone();
+        let interrupts = Arc::new(AtomicU64::new(0));
+        let connect_interrupts = interrupts.clone();
+        let acquire_interrupts = interrupts.clone();
         let pool = SqlitePoolOptions::new()
-            .max_connections(10)
-            .connect(database_url)
+            .max_connections(pool_config.max_connections)
+            .min_connections(pool_config.min_connections)
+            .acquire_timeout(pool_config.acquire_timeout)
+            .idle_timeout(pool_config.idle_timeout)
+            .max_lifetime(pool_config.max_lifetime)
+            .test_before_acquire(settings.test_before_acquire)
+            .after_connect(move |conn, _meta| {
+                let settings = hook_settings.clone();
+                let interrupts = connect_interrupts.clone();
+                Box::pin(async move {
+                    configure_connection(conn, &settings).await?;
+                    watch_interrupts(conn, &interrupts).await
+                })
+            })
+            .before_acquire(move |conn, meta| {
+                let cutoff = *hook_stale_before.lock().unwrap_or_else(PoisonError::into_inner);
+                let opened_at = Instant::now().checked_sub(meta.age);
+                let fresh = match (cutoff, opened_at) {
+                    (Some(cutoff), Some(opened_at)) => opened_at >= cutoff,
+                    _ => true,
+                };
+                let interrupts = acquire_interrupts.clone();
+                Box::pin(async move {
+                    // Nothing to reset until the pool has been interrupted once
+                    if fresh && interrupts.load(Ordering::SeqCst) != 0 {
+                        watch_interrupts(conn, &interrupts).await?;
+                    }
+                    Ok(fresh)
+                })
+            })
+            .connect_with(options)
             .await?;
 
-        Ok(Self { pool })
+        let mut connection = Self::from_pool(pool);
+        connection.stale_before = stale_before;
+        connection.interrupts = interrupts;
+        Ok(connection)
+    }
+
+    pub(crate) fn from_pool(pool: SqlitePool) -> Self {
+        let pool = Arc::new(pool);
+        register_pool(&pool);
+        Self {
+            pool,
+            metrics: Arc::new(PoolMetrics::default()),
+            stale_before: Arc::new(Mutex::new(None)),
+            interrupts: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    // Makes the pool replace every connection that is currently open the
+    // next time it is checked out.
+    pub(crate) fn discard_open_connections(&self) {
+        *self.stale_before.lock().unwrap_or_else(PoisonError::into_inner) = Some(Instant::now());
     }
 
     pub fn pool(&self) -> &SqlitePool {
         &self.pool
     }
 
+    /// Checks a connection out of the pool, counting failed or timed-out
+    /// acquisitions in the pool statistics.
+    pub async fn acquire(&self) -> Result<PoolConnection<Sqlite>> {
+        let started = Instant::now();
+        let conn = self.pool.acquire().await.map_err(|e| {
+            self.metrics.record_acquire_failure();
+            DatabaseError::from(e)
+        })?;
+        self.metrics.record_acquire(started.elapsed());
+        Ok(conn)
+    }
+
+    // Runs a statement, adding its duration to the execute time in the stats.
+    pub(crate) async fn timed<T>(
+        &self,
+        statement: impl Future<Output = std::result::Result<T, sqlx::Error>>,
+    ) -> Result<T> {
+        let started = Instant::now();
+        let result = statement.await;
+        self.metrics.record_execute(started.elapsed());
+        Ok(result?)
+    }
+
+    pub(crate) async fn begin(&self) -> Result<Transaction<'static, Sqlite>> {
+        self.pool.begin().await.map_err(|e| {
+            // BEGIN itself can fail too; only pool errors are acquisition failures
+            if matches!(e, sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed) {
+                self.metrics.record_acquire_failure();
+            }
+            e.into()
+        })
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.pool.size(),
+            idle: self.pool.num_idle(),
+            max_connections: self.pool.options().get_max_connections(),
+            ..self.metrics.snapshot()
+        }
+    }
+
     pub async fn close(self) {
         self.pool.close().await;
     }
@@ -30,6 +406,7 @@ impl DatabaseConnection {
 pub struct Database {
     connection: Option<DatabaseConnection>,
     database_path: String,
+    settings: ConnectionSettings,
 }
 
 impl Database {
@@ -38,6 +415,7 @@ impl Database {
         Self {
             connection: None,
             database_path: path,
+            settings: ConnectionSettings::default(),
         }
     }
 
@@ -45,45 +423,411 @@ impl Database {
         Self {
             connection: None,
             database_path: ":memory:".to_string(),
+            settings: ConnectionSettings::default(),
+        }
+    }
+
+    /// Creates an in-memory database shared by every `Database` in the process
+    /// made with the same `name`, through SQLite's shared cache. The data
+    /// lives as long as at least one of their connections stays open. Names
+    /// may use ASCII letters, digits, `_` and `-`.
+    pub fn new_in_memory_named(name: &str) -> Self {
+        Self {
+            connection: None,
+            database_path: format!("file:{}{}", name, NAMED_MEMORY_PARAMS),
+            settings: ConnectionSettings::default(),
         }
     }
 
+    /// Wraps an existing connection pool, for example one shared with another
+    /// `Database`. Builder options do not apply to a pool that is already
+    /// open, and closing either handle closes the pool for both.
+    pub fn from_connection(connection: DatabaseConnection) -> Self {
+        let options = (*connection.pool().connect_options()).clone();
+        let filename = options.get_filename().to_string_lossy().to_string();
+        // sqlx names in-memory databases `file:sqlx-in-memory-<n>`
+        let database_path = if filename == ":memory:" || filename.starts_with("file:sqlx-in-memory-") {
+            ":memory:".to_string()
+        } else {
+            filename
+        };
+
+        Self {
+            connection: Some(connection),
+            database_path,
+            settings: ConnectionSettings::default(),
+        }
+    }
+
+    /// Whether `self` and `other` are backed by the same connection pool, so
+    /// closing one also closes the other. Uninitialized databases share
+    /// nothing.
+    pub fn shares_pool_with(&self, other: &Database) -> bool {
+        match (&self.connection, &other.connection) {
+            (Some(a), Some(b)) => Arc::ptr_eq(&a.pool, &b.pool),
+            _ => false,
+        }
+    }
+
+    /// Creates a database whose pool holds up to `max_connections` connections
+    /// instead of the default 10.
+    pub fn with_pool_size<P: AsRef<Path>>(database_path: P, max_connections: u32) -> Self {
+        let mut db = Self::new(database_path);
+        db.settings.pool.max_connections = max_connections;
+        db
+    }
+
+    /// Creates a database whose connections wait up to `busy_timeout_ms` for a
+    /// lock held by another connection before failing with `database is
+    /// locked`. The default is 5000ms.
+    pub fn with_busy_timeout<P: AsRef<Path>>(database_path: P, busy_timeout_ms: u32) -> Self {
+        let mut db = Self::new(database_path);
+        db.settings.busy_timeout_ms = busy_timeout_ms;
+        db
+    }
+
+    /// Opens and initializes the database described by `config`, with a pool
+    /// of `pool_size` connections that waits up to `timeout` for one.
+    pub async fn connect_with_config(config: &DatabaseConfig) -> Result<Database> {
+        let pool_config = PoolConfig {
+            max_connections: config.pool_size,
+            acquire_timeout: config.timeout,
+            ..PoolConfig::default()
+        };
+        let mut db = Self::new_with_pool_config(&config.database, pool_config);
+        db.initialize().await?;
+        Ok(db)
+    }
+
+    /// Creates a database whose `execute_*` and `insert` calls are retried
+    /// under `policy` when they fail with `SQLITE_BUSY` or `SQLITE_LOCKED`,
+    /// which can still happen to bursty writers once `busy_timeout` runs out.
+    /// Any other error is returned straight away.
+    pub fn with_retry<P: AsRef<Path>>(database_path: P, policy: RetryPolicy) -> Self {
+        Self::new(database_path).retry_policy(policy)
+    }
+
+    /// Builder form of `with_retry`, for combining it with another constructor.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.settings.retry = Some(policy);
+        self
+    }
+
+    /// Creates a database that reports every `execute_*`, `insert`, `query*`
+    /// and `fetch_one`/`fetch_all`/`fetch_optional` call taking `threshold`
+    /// or longer, as a `tracing` warning or to the callback set with
+    /// `on_slow_query`.
+    pub fn with_slow_query_threshold<P: AsRef<Path>>(database_path: P, threshold: Duration) -> Self {
+        let mut db = Self::new(database_path);
+        db.settings.slow_query = Some(SlowQuery {
+            threshold,
+            callback: None,
+        });
+        db
+    }
+
+    /// Calls `callback` with the SQL, normalized by `normalize_sql`, and its
+    /// duration for each slow query instead of logging it. Without
+    /// `with_slow_query_threshold`, any query taking a second or longer
+    /// counts as slow.
+    pub fn on_slow_query<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&str, Duration) + Send + Sync + 'static,
+    {
+        let threshold = self.settings.slow_query.as_ref().map_or(Duration::from_secs(1), |slow| slow.threshold);
+        self.settings.slow_query = Some(SlowQuery {
+            threshold,
+            callback: Some(Arc::new(callback)),
+        });
+        self
+    }
+
+    /// Creates a database that only opens an existing file: `initialize`
+    /// fails with `Connection` instead of creating an empty database (or its
+    /// parent directories) when `database_path` does not exist.
+    pub fn new_existing<P: AsRef<Path>>(database_path: P) -> Self {
+        let mut db = Self::new(database_path);
+        db.settings.create_if_missing = false;
+        db
+    }
+
+    /// Creates a database with foreign key enforcement on (the default) or off
+    /// for every pooled connection.
+    pub fn with_foreign_keys<P: AsRef<Path>>(database_path: P, enabled: bool) -> Self {
+        let mut db = Self::new(database_path);
+        db.settings.foreign_keys = enabled;
+        db
+    }
+
+    /// Creates a database whose file is encrypted with SQLCipher using `key`.
+    /// Opening an existing file with the wrong key fails with `Connection`.
+    #[cfg(feature = "sqlcipher")]
+    pub fn new_encrypted<P: AsRef<Path>>(database_path: P, key: &str) -> Self {
+        let mut db = Self::new(database_path);
+        db.settings.encryption_key = Some(key.to_string());
+        db
+    }
+
+    /// Creates a database that switches to `journal_mode` when it connects.
+    /// `JournalMode::Wal` also sets `synchronous = NORMAL`; it is ignored for
+    /// in-memory databases, which cannot use WAL.
+    pub fn new_with_journal_mode<P: AsRef<Path>>(database_path: P, journal_mode: JournalMode) -> Self {
+        let mut db = Self::new(database_path);
+        db.settings.journal_mode = Some(journal_mode);
+        db
+    }
+
+    pub fn new_with_pool_config<P: AsRef<Path>>(database_path: P, pool_config: PoolConfig) -> Self {
+        let mut db = Self::new(database_path);
+        db.settings.pool = pool_config;
+        db
+    }
+
+    /// Pings each connection before the pool lends it out (enabled by default).
+    /// This discards connections broken by e.g. a filesystem hiccup at the cost
+    /// of an extra round-trip on every acquire; disable it for latency-sensitive
+    /// workloads. Takes effect on the next `initialize`.
+    pub fn test_before_acquire(mut self, enabled: bool) -> Self {
+        self.settings.test_before_acquire = enabled;
+        self
+    }
+
+    /// Sets SQLite's bound-variable limit (`SQLITE_LIMIT_VARIABLE_NUMBER`) on
+    /// every pooled connection, and sizes the statements built by helpers such
+    /// as `insert_many` to match. SQLite caps the value at its compile-time
+    /// maximum, 32766 for the bundled library. Takes effect on the next
+    /// `initialize`.
+    pub fn max_variable_number(mut self, limit: u32) -> Self {
+        self.settings.max_variable_number = Some(limit);
+        self
+    }
+
+    /// Registers a collation usable as `ORDER BY name COLLATE <name>` on every
+    /// pooled connection. Can be called repeatedly to register several. The
+    /// comparison runs on whichever thread executes the query and is shared by
+    /// all connections, hence the `Send + Sync + 'static` bounds. Takes effect
+    /// on the next `initialize`.
+    pub fn register_collation<F>(mut self, name: &str, compare: F) -> Self
+    where
+        F: Fn(&str, &str) -> CmpOrdering + Send + Sync + 'static,
+    {
+        self.settings.collations.push((name.to_string(), Arc::new(compare)));
+        self
+    }
+
+    /// Chooses how `SqlValue::DateTime` parameters are stored: RFC 3339 text
+    /// (the default) or Unix seconds.
+    pub fn datetime_storage(mut self, storage: DateTimeStorage) -> Self {
+        self.settings.encoding.datetime = storage;
+        self
+    }
+
+    /// Chooses how `SqlValue::Bool` parameters are stored: `0`/`1` integers
+    /// (the default) or `'true'`/`'false'` text. Use `SqlValue::as_bool` to
+    /// read either format back.
+    pub fn bool_storage(mut self, storage: BoolStorage) -> Self {
+        self.settings.encoding.bool = storage;
+        self
+    }
+
+    /// Logs every failed `execute_*`, `query*` and `fetch_*` call as a
+    /// `tracing` error with the SQL, its parameters (blobs are redacted to
+    /// their length) and the error. Off by default.
+    pub fn log_failed_queries(mut self, enabled: bool) -> Self {
+        self.settings.log_failed_queries = enabled;
+        self
+    }
+
+    pub(crate) fn read_only(mut self, enabled: bool) -> Self {
+        self.settings.read_only = enabled;
+        self
+    }
+
+    /// Turns a user-supplied database path into an absolute, normalized one:
+    /// a leading `~` becomes the home directory and relative paths are
+    /// resolved against `allowed_root`, or the current directory if there is
+    /// none. With `allowed_root`, paths that end up outside it (for example
+    /// through `..`) are rejected with `InvalidData`. Nothing is read from the
+    /// filesystem, so the path need not exist yet.
+    pub fn resolve_path(input: &str, allowed_root: Option<&Path>) -> Result<PathBuf> {
+        if input.trim().is_empty() {
+            return Err(DatabaseError::PathResolution("Database path is empty".to_string()));
+        }
+
+        let home =
+            || dirs::home_dir().ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()));
+        let expanded = if input == "~" {
+            home()?
+        } else if let Some(rest) = input.strip_prefix("~/").or_else(|| input.strip_prefix("~\\")) {
+            home()?.join(rest)
+        } else {
+            PathBuf::from(input)
+        };
+
+        let base = match allowed_root {
+            Some(root) => normalize_path(&std::path::absolute(root)?),
+            None => std::env::current_dir()?,
+        };
+        let resolved = normalize_path(&base.join(expanded));
+
+        if allowed_root.is_some() && !resolved.starts_with(&base) {
+            return Err(DatabaseError::InvalidData {
+                message: format!("Path {:?} is outside the allowed root {}", input, base.display()),
+            });
+        }
+        Ok(resolved)
+    }
+
     pub fn new_default() -> Result<Self> {
         let default_path = get_default_database_path()?;
         Ok(Self::new(default_path))
     }
 
+    /// Like `new_default`, but under the directory for `app_name`; see
+    /// `get_default_database_path_for`.
+    pub fn new_default_for_app(app_name: &str) -> Result<Self> {
+        let default_path = get_default_database_path_for(app_name)?;
+        Ok(Self::new(default_path))
+    }
+
     pub async fn new_default_initialized() -> Result<Self> {
         let default_path = get_default_database_path()?;
-
-        create_directory_if_not_exists(&default_path)?;
-
         let mut db = Self::new(default_path);
         db.initialize().await?;
         Ok(db)
     }
 
+    /// Opens the default file database, falling back to an in-memory database
+    /// if the default path cannot be resolved, created or opened. The flag is
+    /// `true` when the fallback was used, in which case nothing is persisted.
+    pub async fn new_default_or_memory() -> Result<(Self, bool)> {
+        match Self::new_default_initialized().await {
+            Ok(db) => Ok((db, false)),
+            Err(DatabaseError::PathResolution(_))
+            | Err(DatabaseError::DirectoryCreation(_))
+            | Err(DatabaseError::Connection(_))
+            | Err(DatabaseError::Io(_)) => {
+                let mut db = Self::new_in_memory();
+                db.initialize().await?;
+                Ok((db, true))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Opens the connection pool. For file databases, missing parent
+    /// directories are created first (failing with `DirectoryCreation`) and
+    /// SQLite creates the file itself if it does not exist yet, unless the
+    /// database was made with `new_existing`.
     pub async fn initialize(&mut self) -> Result<()> {
-        let database_url = if self.database_path == ":memory:" {
-            "sqlite::memory:".to_string()
+        if self.settings.max_variable_number == Some(0) {
+            return Err(DatabaseError::InvalidData {
+                message: "max_variable_number must be at least 1".to_string(),
+            });
+        }
+
+        // Paths are passed structurally, so spaces, `?` and `#` need no escaping
+        let options = if let Some(name) = self.memory_name() {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                return Err(DatabaseError::InvalidData {
+                    message: format!("Invalid in-memory database name: {:?}", name),
+                });
+            }
+            SqliteConnectOptions::from_str(&format!("sqlite:{}", self.database_path))?
+        } else if self.is_in_memory() {
+            SqliteConnectOptions::from_str("sqlite::memory:")?
         } else {
-            // Normalize path separators for SQLite URL
-            let normalized_path = self.database_path.replace('\\', "/");
-            format!("sqlite:{}", normalized_path)
+            let create = self.settings.create_if_missing && !self.settings.read_only;
+            if create {
+                create_directory_if_not_exists(Path::new(&self.database_path))?;
+            }
+            SqliteConnectOptions::new()
+                .filename(&self.database_path)
+                .read_only(self.settings.read_only)
+                .create_if_missing(create)
         };
 
-        let connection = DatabaseConnection::new(&database_url).await?;
+        let mut settings = self.settings.clone();
+        if self.is_in_memory() && settings.journal_mode == Some(JournalMode::Wal) {
+            settings.journal_mode = None;
+        }
+
+        let connection = DatabaseConnection::connect_with(options, &settings).await?;
 
         self.connection = Some(connection);
         Ok(())
     }
 
+    pub(crate) fn settings(&self) -> &ConnectionSettings {
+        &self.settings
+    }
+
+    pub(crate) fn encoding(&self) -> ValueEncoding {
+        self.settings.encoding
+    }
+
+    /// The path this database was created with, `:memory:`, or the `file:` URI
+    /// of a `new_in_memory_named` database.
+    pub fn path(&self) -> &str {
+        &self.database_path
+    }
+
+    pub(crate) fn take_connection(&mut self) -> Option<DatabaseConnection> {
+        self.connection.take()
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        self.database_path == ":memory:" || self.memory_name().is_some()
+    }
+
+    // The name of a database made with `new_in_memory_named`
+    fn memory_name(&self) -> Option<&str> {
+        self.database_path.strip_prefix("file:")?.strip_suffix(NAMED_MEMORY_PARAMS)
+    }
+
     pub fn connection(&self) -> Result<&DatabaseConnection> {
         self.connection
             .as_ref()
             .ok_or(DatabaseError::NotInitialized)
     }
 
+    /// A handle that interrupts the statements running on this database's
+    /// connections; see `InterruptHandle`.
+    pub fn interrupt_handle(&self) -> Result<InterruptHandle> {
+        Ok(InterruptHandle {
+            interrupts: self.connection()?.interrupts.clone(),
+        })
+    }
+
+    /// Whether `initialize` has run and the pool has not been closed since,
+    /// for example through a `DatabaseConnection` sharing it.
+    pub fn is_initialized(&self) -> bool {
+        self.connection.as_ref().is_some_and(|connection| !connection.pool().is_closed())
+    }
+
+    /// Checks that the database answers `SELECT 1`, for liveness probes.
+    /// Fails with `NotInitialized` before `initialize` and `Connection` if the
+    /// pool has been closed or the query fails.
+    pub async fn ping(&self) -> Result<()> {
+        let connection = self.connection()?;
+        if connection.pool().is_closed() {
+            return Err(DatabaseError::Connection(sqlx::Error::PoolClosed));
+        }
+        let mut conn = connection.acquire().await?;
+        sqlx::query("SELECT 1").execute(&mut *conn).await?;
+        Ok(())
+    }
+
+    /// Total connections currently open across every pool created by this
+    /// crate in the process, for detecting databases that were never closed.
+    pub fn global_open_connections() -> usize {
+        global_open_connections()
+    }
+
+    pub fn pool_stats(&self) -> Result<PoolStats> {
+        Ok(self.connection()?.stats())
+    }
+
     pub async fn create_tables(&self) -> Result<()> {
         let _conn = self.connection()?;
 
@@ -97,70 +841,793 @@ impl Database {
         Ok(())
     }
 
+    /// Closes the current pool, if any, and initializes a new one for the
+    /// same path and settings, for recovering from a closed pool or stale
+    /// file handles. Other handles sharing the old pool see it closed. An
+    /// in-memory database comes back empty, since its data lived in the
+    /// old pool.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        if let Some(connection) = self.connection.take() {
+            connection.close().await;
+        }
+        self.initialize().await
+    }
+
     pub async fn execute_query(&self, query: &str) -> Result<sqlx::sqlite::SqliteQueryResult> {
-        let conn = self.connection()?;
-        let result = sqlx::query(query).execute(conn.pool()).await?;
-        Ok(result)
+        let execute = self.with_busy_retry(|| {
+            self.with_directory_recovery(|| async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                connection.timed(sqlx::query(query).execute(&mut *conn)).await
+            })
+        });
+        let result = self.traced(query, |result| result.rows_affected(), execute).await;
+        self.log_if_failed(query, String::new, result)
+    }
+
+    /// Runs a script of `;`-separated statements, such as a schema file, in
+    /// order on one connection. Stops at the first failing statement with a
+    /// `Query` error naming it; the statements before it stay applied, so
+    /// wrap the script in `BEGIN`/`COMMIT` if it must be all or nothing. A
+    /// transaction the script left open is rolled back on failure.
+    pub async fn execute_batch(&self, sql: &str) -> Result<()> {
+        let statements = split_statements(sql);
+        let connection = self.connection()?;
+        let mut conn = connection.acquire().await?;
+
+        for (index, statement) in statements.iter().enumerate() {
+            let result = connection.timed(sqlx::query(statement).execute(&mut *conn)).await;
+            if let Err(e) = self.log_if_failed(statement, String::new, result) {
+                abandon_open_transaction(conn).await;
+                return Err(DatabaseError::Query(format!(
+                    "Statement {} of {} failed: {} (in `{}`)",
+                    index + 1,
+                    statements.len(),
+                    e,
+                    statement
+                )));
+            }
+        }
+        Ok(())
     }
 
     pub async fn execute_query_with_params(&self, query: &str, params: Vec<String>) -> Result<sqlx::sqlite::SqliteQueryResult> {
-        let conn = self.connection()?;
-        let mut query_builder = sqlx::query(query);
+        let execute = self.with_busy_retry(|| {
+            self.with_directory_recovery(|| async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                let mut query_builder = sqlx::query(query);
 
-        for param in params {
-            query_builder = query_builder.bind(param);
+                for param in &params {
+                    query_builder = query_builder.bind(param.clone());
+                }
+
+                connection.timed(query_builder.execute(&mut *conn)).await
+            })
+        });
+        let result = self.traced(query, |result| result.rows_affected(), execute).await;
+        self.log_if_failed(query, || describe_text_params(&params), result)
+    }
+
+    /// Runs `query` and returns the number of rows it changed, the usual
+    /// result wanted from an `UPDATE` or `DELETE`.
+    pub async fn execute_count(&self, query: &str) -> Result<u64> {
+        Ok(self.execute_query(query).await?.rows_affected())
+    }
+
+    /// Like `execute_count`, with `params` bound in order.
+    pub async fn execute_count_with(&self, query: &str, params: &[SqlValue]) -> Result<u64> {
+        Ok(self.execute_params(query, params).await?.rows_affected())
+    }
+
+    /// Runs an `INSERT` with `params` bound in order and returns the rowid of
+    /// the new row. Fails with `Query` if nothing was inserted (for example
+    /// `INSERT OR IGNORE` hitting a conflict), where SQLite would otherwise
+    /// report the rowid of an earlier insert.
+    pub async fn insert(&self, query: &str, params: &[SqlValue]) -> Result<i64> {
+        let result = self.execute_params(query, params).await?;
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::Query(format!("Insert affected no rows: {}", query)));
+        }
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Runs any single statement with `params` bound in order and returns a
+    /// `QueryResult`: the rows it returned as JSON, the rows it changed, and
+    /// for an `INSERT` or `REPLACE` that changed rows, the new rowid.
+    pub async fn run(&self, query: &str, params: &[SqlValue]) -> Result<QueryResult> {
+        let run = async {
+            let connection = self.connection()?;
+            let mut conn = connection.acquire().await?;
+            let arguments = to_arguments(params, self.settings.encoding);
+            let results = conn.fetch_many(sqlx::query_with(query, arguments)).try_collect::<Vec<_>>();
+            let mut result = QueryResult::default();
+            let mut last_insert_rowid = 0;
+            for item in connection.timed(results).await? {
+                match item {
+                    sqlx::Either::Left(done) => {
+                        result.rows_affected += done.rows_affected();
+                        last_insert_rowid = done.last_insert_rowid();
+                    }
+                    sqlx::Either::Right(row) => result.rows.push(row_to_json(&row)?.into_iter().collect()),
+                }
+            }
+
+            // SQLite keeps reporting the last insert for any later statement
+            let keyword = normalize_sql(query).split(' ').next().unwrap_or_default().to_ascii_uppercase();
+            if result.rows_affected > 0 && (keyword == "INSERT" || keyword == "REPLACE") {
+                result.last_insert_id = Some(last_insert_rowid);
+            }
+            Ok(result)
+        };
+        let result = self.traced(query, |result| result.rows.len() as u64, run).await;
+        self.log_if_failed(query, || describe_params(params), result)
+    }
+
+    // Runs a statement with `SqlValue` parameters bound using the configured encoding.
+    async fn execute_params(&self, query: &str, params: &[SqlValue]) -> Result<sqlx::sqlite::SqliteQueryResult> {
+        let execute = self.with_busy_retry(|| {
+            self.with_directory_recovery(|| async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                let arguments = to_arguments(params, self.settings.encoding);
+                connection.timed(sqlx::query_with(query, arguments).execute(&mut *conn)).await
+            })
+        });
+        let result = self.traced(query, |result| result.rows_affected(), execute).await;
+        self.log_if_failed(query, || describe_params(params), result)
+    }
+
+    // Runs a statement, reporting it to the slow-query callback if it takes
+    // at least the configured threshold. With the `tracing` feature it also
+    // runs in a `query` span recording the (truncated) SQL, the duration and
+    // the rows returned or affected, and failures are logged at `warn`.
+    #[cfg(feature = "tracing")]
+    async fn traced<T>(
+        &self,
+        query: &str,
+        rows: impl FnOnce(&T) -> u64,
+        operation: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        use tracing::Instrument;
+
+        const MAX_SQL_CHARS: usize = 200;
+        let sql = match query.char_indices().nth(MAX_SQL_CHARS) {
+            Some((end, _)) => format!("{}...", &query[..end]),
+            None => query.to_string(),
+        };
+        let span = tracing::info_span!(
+            "query",
+            sql,
+            duration_ms = tracing::field::Empty,
+            rows = tracing::field::Empty
+        );
+
+        let started = Instant::now();
+        let result = operation.instrument(span.clone()).await;
+        let elapsed = started.elapsed();
+        span.record("duration_ms", elapsed.as_secs_f64() * 1000.0);
+        match &result {
+            Ok(value) => {
+                span.record("rows", rows(value));
+            }
+            Err(e) => span.in_scope(|| tracing::warn!(error = %e, "query failed")),
+        }
+        self.report_if_slow(query, elapsed);
+        result
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    async fn traced<T>(
+        &self,
+        query: &str,
+        _rows: impl FnOnce(&T) -> u64,
+        operation: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        if self.settings.slow_query.is_none() {
+            return operation.await;
+        }
+        let started = Instant::now();
+        let result = operation.await;
+        self.report_if_slow(query, started.elapsed());
+        result
+    }
+
+    fn report_if_slow(&self, query: &str, elapsed: Duration) {
+        let Some(slow_query) = &self.settings.slow_query else {
+            return;
+        };
+        if elapsed < slow_query.threshold {
+            return;
+        }
+        // Report the normalized form so repeats of a query differing only in
+        // literals share one key
+        let query = normalize_sql(query);
+        match &slow_query.callback {
+            Some(callback) => callback(&query, elapsed),
+            None => tracing::warn!(sql = query, elapsed_ms = elapsed.as_millis() as u64, "slow query"),
+        }
+    }
+
+    // Emits the `log_failed_queries` error event; `params` is only rendered
+    // when there is something to log.
+    fn log_if_failed<T>(&self, query: &str, params: impl FnOnce() -> String, result: Result<T>) -> Result<T> {
+        if self.settings.log_failed_queries {
+            if let Err(e) = &result {
+                tracing::error!(sql = query, params = %params(), error = %e, "query failed");
+            }
+        }
+        result
+    }
+
+    // Runs a write under the `RetryPolicy`, if any, repeating it with
+    // exponential backoff while it fails because the database is busy.
+    async fn with_busy_retry<F, Fut, T>(&self, operation: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let Some(policy) = self.settings.retry else {
+            return operation().await;
+        };
+
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Err(e) if e.is_busy() && attempt < policy.max_attempts => {
+                    tokio::time::sleep(policy.backoff.saturating_mul(1 << (attempt - 1).min(16))).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // Runs a write and, if it fails because the directory of a database under
+    // the default location has been deleted, recreates the directory once and
+    // retries. The old file went with the directory, so the retry runs against
+    // a new, empty database on fresh connections.
+    async fn with_directory_recovery<F, Fut, T>(&self, operation: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let result = operation().await;
+        if result.is_err() && self.recreate_missing_directory() {
+            return operation().await;
+        }
+        result
+    }
+
+    fn recreate_missing_directory(&self) -> bool {
+        if self.is_in_memory() {
+            return false;
         }
 
-        let result = query_builder.execute(conn.pool()).await?;
-        Ok(result)
+        let path = Path::new(&self.database_path);
+        let Some(parent) = path.parent() else {
+            return false;
+        };
+        let under_default_location = get_default_database_path()
+            .ok()
+            .and_then(|default_path| default_path.parent().map(|dir| path.starts_with(dir)))
+            .unwrap_or(false);
+
+        if !under_default_location || parent.exists() {
+            return false;
+        }
+        if create_directory_if_not_exists(path).is_err() || std::fs::File::create(path).is_err() {
+            return false;
+        }
+
+        // Existing connections still refer to the deleted file
+        if let Some(connection) = &self.connection {
+            connection.discard_open_connections();
+        }
+        true
     }
 
     pub async fn query(&self, query: &str) -> Result<Vec<sqlx::sqlite::SqliteRow>> {
-        let conn = self.connection()?;
-        let rows = sqlx::query(query).fetch_all(conn.pool()).await?;
-        Ok(rows)
+        let result = self
+            .traced(query, |rows| rows.len() as u64, async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                connection.timed(sqlx::query(query).fetch_all(&mut *conn)).await
+            })
+            .await;
+        self.log_if_failed(query, String::new, result)
     }
 
     pub async fn query_with_params(&self, query: &str, params: Vec<String>) -> Result<Vec<sqlx::sqlite::SqliteRow>> {
-        let conn = self.connection()?;
-        let mut query_builder = sqlx::query(query);
+        let result = self
+            .traced(query, |rows| rows.len() as u64, async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                let mut query_builder = sqlx::query(query);
 
-        for param in params {
-            query_builder = query_builder.bind(param);
-        }
+                for param in &params {
+                    query_builder = query_builder.bind(param.clone());
+                }
 
-        let rows = query_builder.fetch_all(conn.pool()).await?;
-        Ok(rows)
+                connection.timed(query_builder.fetch_all(&mut *conn)).await
+            })
+            .await;
+        self.log_if_failed(query, || describe_text_params(&params), result)
+    }
+
+    /// Fetches every row of `query` as a JSON object keyed by column name,
+    /// for queries without a matching `FromRow` type. Integers and reals map
+    /// to numbers, text to strings, blobs to base64 strings and NULL to null.
+    pub async fn fetch_all_json(&self, query: &str) -> Result<Vec<serde_json::Value>> {
+        self.query(query)
+            .await?
+            .iter()
+            .map(|row| Ok(serde_json::Value::Object(row_to_json(row)?)))
+            .collect()
     }
 
     pub async fn fetch_one<T>(&self, query: &str) -> Result<T>
     where
         T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
     {
-        let conn = self.connection()?;
-        let result = sqlx::query_as::<_, T>(query).fetch_one(conn.pool()).await?;
-        Ok(result)
+        let result = self
+            .traced(query, |_| 1, async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                connection.timed(sqlx::query_as::<_, T>(query).fetch_one(&mut *conn)).await
+            })
+            .await;
+        self.log_if_failed(query, String::new, result)
     }
 
     pub async fn fetch_all<T>(&self, query: &str) -> Result<Vec<T>>
     where
         T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
     {
-        let conn = self.connection()?;
-        let results = sqlx::query_as::<_, T>(query).fetch_all(conn.pool()).await?;
-        Ok(results)
+        let result = self
+            .traced(query, |rows| rows.len() as u64, async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                connection.timed(sqlx::query_as::<_, T>(query).fetch_all(&mut *conn)).await
+            })
+            .await;
+        self.log_if_failed(query, String::new, result)
+    }
+
+    pub(crate) async fn fetch_all_with<T>(&self, query: &str, params: &[SqlValue]) -> Result<Vec<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    {
+        let result = self
+            .traced(query, |rows| rows.len() as u64, async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                let arguments = to_arguments(params, self.settings.encoding);
+                connection.timed(sqlx::query_as_with(query, arguments).fetch_all(&mut *conn)).await
+            })
+            .await;
+        self.log_if_failed(query, || describe_params(params), result)
     }
 
     pub async fn fetch_optional<T>(&self, query: &str) -> Result<Option<T>>
     where
         T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
     {
-        let conn = self.connection()?;
-        let result = sqlx::query_as::<_, T>(query).fetch_optional(conn.pool()).await?;
-        Ok(result)
+        let result = self
+            .traced(query, |row| row.is_some() as u64, async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                connection.timed(sqlx::query_as::<_, T>(query).fetch_optional(&mut *conn)).await
+            })
+            .await;
+        self.log_if_failed(query, String::new, result)
+    }
+
+    /// Like `fetch_all`, but fails with `Timeout` if the query runs longer
+    /// than `timeout`. The statement is interrupted rather than left running,
+    /// and the connection returns to the pool once it has stopped.
+    pub async fn fetch_all_timeout<T>(&self, query: &str, timeout: Duration) -> Result<Vec<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    {
+        let result = self
+            .traced(query, |rows| rows.len() as u64, async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                let raw = RawConnection::of(&mut conn).await?;
+                let statement = connection.timed(sqlx::query_as::<_, T>(query).fetch_all(&mut *conn));
+                raw.stop_if_elapsed(tokio::time::timeout(timeout, statement).await, &mut conn).await
+            })
+            .await;
+        self.log_if_failed(query, String::new, result)
+    }
+
+    /// Like `fetch_one`, with the timeout of `fetch_all_timeout`.
+    pub async fn fetch_one_timeout<T>(&self, query: &str, timeout: Duration) -> Result<T>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    {
+        let result = self
+            .traced(query, |_| 1, async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                let raw = RawConnection::of(&mut conn).await?;
+                let statement = connection.timed(sqlx::query_as::<_, T>(query).fetch_one(&mut *conn));
+                raw.stop_if_elapsed(tokio::time::timeout(timeout, statement).await, &mut conn).await
+            })
+            .await;
+        self.log_if_failed(query, String::new, result)
+    }
+
+    /// Like `execute_query`, with the timeout of `fetch_all_timeout`.
+    pub async fn execute_query_timeout(
+        &self,
+        query: &str,
+        timeout: Duration,
+    ) -> Result<sqlx::sqlite::SqliteQueryResult> {
+        let result = self
+            .traced(query, |result| result.rows_affected(), async {
+                let connection = self.connection()?;
+                let mut conn = connection.acquire().await?;
+                let raw = RawConnection::of(&mut conn).await?;
+                let statement = connection.timed(sqlx::query(query).execute(&mut *conn));
+                raw.stop_if_elapsed(tokio::time::timeout(timeout, statement).await, &mut conn).await
+            })
+            .await;
+        self.log_if_failed(query, String::new, result)
+    }
+
+    /// Streams the rows of `query` lazily instead of collecting them, for
+    /// result sets too large to hold in memory. The stream borrows the pool,
+    /// so each row is fetched as it is polled.
+    pub fn fetch_stream<'a, T>(&'a self, query: &'a str) -> impl Stream<Item = Result<T>> + 'a
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin + 'a,
+    {
+        match self.connection() {
+            Ok(connection) => sqlx::query_as::<_, T>(query)
+                .fetch(connection.pool())
+                .map(|row| row.map_err(DatabaseError::from))
+                .left_stream(),
+            Err(e) => stream::once(future::ready(Err(e))).right_stream(),
+        }
+    }
+
+    /// Deletes rows matching `where_clause` at most `batch_size` at a time so
+    /// the write lock is released between batches. Returns the total deleted.
+    pub async fn delete_in_batches(
+        &self,
+        table: &str,
+        where_clause: &str,
+        params: &[SqlValue],
+        batch_size: u32,
+    ) -> Result<u64> {
+        let connection = self.connection()?;
+        let table = quote_identifier(table)?;
+
+        if batch_size == 0 {
+            return Err(DatabaseError::InvalidData {
+                message: "batch_size must be at least 1".to_string(),
+            });
+        }
+
+        let where_clause = if where_clause.trim().is_empty() { "1" } else { where_clause };
+
+        // DELETE ... LIMIT is only available when SQLite is compiled with
+        // SQLITE_ENABLE_UPDATE_DELETE_LIMIT; otherwise limit through a rowid subquery.
+        let (supports_limit,): (bool,) =
+            sqlx::query_as("SELECT sqlite_compileoption_used('ENABLE_UPDATE_DELETE_LIMIT')")
+                .fetch_one(&mut *connection.acquire().await?)
+                .await?;
+
+        let query = if supports_limit {
+            format!("DELETE FROM {} WHERE {} LIMIT {}", table, where_clause, batch_size)
+        } else {
+            format!(
+                "DELETE FROM {table} WHERE rowid IN (SELECT rowid FROM {table} WHERE {} LIMIT {})",
+                where_clause, batch_size
+            )
+        };
+
+        let mut total_deleted = 0;
+        loop {
+            let result = sqlx::query_with(&query, to_arguments(params, self.settings.encoding))
+                .execute(&mut *connection.acquire().await?)
+                .await?;
+
+            total_deleted += result.rows_affected();
+            if result.rows_affected() < batch_size as u64 {
+                break;
+            }
+
+            tokio::task::yield_now().await;
+        }
+
+        Ok(total_deleted)
+    }
+
+    /// Runs `UPDATE table SET set WHERE where_clause` and returns the
+    /// `id_column` values of the rows that were changed. `params` are bound to
+    /// the placeholders of `set` followed by those of `where_clause`.
+    pub async fn update_returning_ids(
+        &self,
+        table: &str,
+        set: &str,
+        where_clause: &str,
+        params: &[SqlValue],
+        id_column: &str,
+    ) -> Result<Vec<i64>> {
+        let mut conn = self.connection()?.acquire().await?;
+        let table = quote_identifier(table)?;
+        let id_column = quote_identifier(id_column)?;
+        let where_clause = if where_clause.trim().is_empty() { "1" } else { where_clause };
+
+        let query = format!(
+            "UPDATE {} SET {} WHERE {} RETURNING {}",
+            table, set, where_clause, id_column
+        );
+        let ids: Vec<(i64,)> = sqlx::query_as_with(&query, to_arguments(params, self.settings.encoding))
+            .fetch_all(&mut *conn)
+            .await?;
+
+        Ok(ids.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Fetches the rows of `table` whose `id_column` is one of `ids`. Large id
+    /// lists are split across several `IN (...)` queries.
+    pub async fn fetch_by_ids<T>(&self, table: &str, id_column: &str, ids: &[SqlValue]) -> Result<Vec<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    {
+        let rows = self.fetch_rows_by_ids(table, id_column, ids).await?;
+        Ok(rows.iter().map(T::from_row).collect::<std::result::Result<_, _>>()?)
+    }
+
+    /// Like `fetch_by_ids`, but returns the rows keyed by the integer value of
+    /// `id_column`. Ids with no matching row are absent from the map.
+    pub async fn fetch_map_by_ids<T>(&self, table: &str, id_column: &str, ids: &[SqlValue]) -> Result<HashMap<i64, T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    {
+        let rows = self.fetch_rows_by_ids(table, id_column, ids).await?;
+        let mut results = HashMap::with_capacity(rows.len());
+        for row in &rows {
+            results.insert(row.try_get::<i64, _>(id_column)?, T::from_row(row)?);
+        }
+        Ok(results)
+    }
+
+    /// Runs `query` and returns its result column by column, for feeding
+    /// dataframe libraries or aggregating without per-row structs.
+    pub async fn fetch_columnar(&self, query: &str, params: &[SqlValue]) -> Result<ColumnBatch> {
+        let mut conn = self.connection()?.acquire().await?;
+        let rows = sqlx::query_with(query, to_arguments(params, self.settings.encoding))
+            .fetch_all(&mut *conn)
+            .await?;
+
+        // Without a row to read them from, take the column names from the prepared statement
+        let names = match rows.first() {
+            Some(row) => row.columns().iter().map(|column| column.name().to_string()).collect(),
+            None => (&mut *conn)
+                .describe(query)
+                .await?
+                .columns()
+                .iter()
+                .map(|column| column.name().to_string())
+                .collect(),
+        };
+        let rows = rows.iter().map(decode_row).collect::<Result<Vec<_>>>()?;
+
+        Ok(ColumnBatch::from_rows(names, rows))
+    }
+
+    async fn fetch_rows_by_ids(&self, table: &str, id_column: &str, ids: &[SqlValue]) -> Result<Vec<SqliteRow>> {
+        let mut conn = self.connection()?.acquire().await?;
+        let table = quote_identifier(table)?;
+        let id_column = quote_identifier(id_column)?;
+
+        let mut results = Vec::with_capacity(ids.len());
+        for chunk in chunk_params(ids, 1, self.settings.max_variables()) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let query = format!("SELECT * FROM {} WHERE {} IN ({})", table, id_column, placeholders);
+            let rows = sqlx::query_with(&query, to_arguments(chunk, self.settings.encoding))
+                .fetch_all(&mut *conn)
+                .await?;
+            results.extend(rows);
+        }
+
+        Ok(results)
+    }
+
+    /// Inserts `rows` into `columns` of `table` using multi-row `INSERT`
+    /// statements sized to the bound-variable limit. All statements run in one
+    /// transaction. Returns the number of rows inserted.
+    pub async fn insert_many(&self, table: &str, columns: &[&str], rows: &[Vec<SqlValue>]) -> Result<u64> {
+        let statements = self.insert_statements(table, columns, rows)?;
+        let mut tx = self.connection()?.begin().await?;
+        let mut inserted = 0;
+
+        for (query, chunk) in statements {
+            let result = sqlx::query_with(&query, to_arguments(&chunk.concat(), self.settings.encoding))
+                .execute(&mut *tx)
+                .await?;
+            inserted += result.rows_affected();
+        }
+
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    /// Like `insert_many`, but returns the rowid of each inserted row in the
+    /// order of `rows`. SQLite does not promise an order for `RETURNING`
+    /// rows, so the rows are inserted one statement at a time, in one
+    /// transaction, reading back each rowid.
+    pub async fn insert_many_returning_ids(
+        &self,
+        table: &str,
+        columns: &[&str],
+        rows: &[Vec<SqlValue>],
+    ) -> Result<Vec<i64>> {
+        let (prefix, row_placeholders) = self.insert_target(table, columns, rows)?;
+        let query = format!("{} {}", prefix, row_placeholders);
+        let mut tx = self.connection()?.begin().await?;
+        let mut ids = Vec::with_capacity(rows.len());
+
+        for row in rows {
+            let result = sqlx::query_with(&query, to_arguments(row, self.settings.encoding))
+                .execute(&mut *tx)
+                .await?;
+            ids.push(result.last_insert_rowid());
+        }
+
+        tx.commit().await?;
+        Ok(ids)
+    }
+
+    /// Inserts one row, or updates the non-key columns of the row whose
+    /// `key_columns` already match, with `INSERT ... ON CONFLICT DO UPDATE`.
+    /// `key_columns` must be a subset of `columns` covered by a primary key or
+    /// unique index, and `params` holds one value per entry of `columns`.
+    /// Returns the number of rows changed.
+    pub async fn upsert(
+        &self,
+        table: &str,
+        key_columns: &[&str],
+        columns: &[&str],
+        params: &[SqlValue],
+    ) -> Result<u64> {
+        if key_columns.is_empty() {
+            return Err(DatabaseError::InvalidData {
+                message: "upsert requires at least one key column".to_string(),
+            });
+        }
+        if let Some(key) = key_columns.iter().find(|key| !columns.contains(key)) {
+            return Err(DatabaseError::InvalidData {
+                message: format!("Key column {:?} is not among the upserted columns", key),
+            });
+        }
+        if params.len() != columns.len() {
+            return Err(DatabaseError::InvalidData {
+                message: format!("Expected {} values, got {}", columns.len(), params.len()),
+            });
+        }
+
+        let quote_all = |names: &[&str]| names.iter().map(|name| quote_identifier(name)).collect::<Result<Vec<_>>>();
+        let table = quote_identifier(table)?;
+        let keys = quote_all(key_columns)?;
+        let updates = quote_all(&columns.iter().filter(|c| !key_columns.contains(c)).copied().collect::<Vec<_>>())?;
+        let action = if updates.is_empty() {
+            "NOTHING".to_string()
+        } else {
+            let assignments: Vec<String> = updates.iter().map(|c| format!("{} = excluded.{}", c, c)).collect();
+            format!("UPDATE SET {}", assignments.join(", "))
+        };
+
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) DO {}",
+            table,
+            quote_all(columns)?.join(", "),
+            vec!["?"; columns.len()].join(", "),
+            keys.join(", "),
+            action
+        );
+        Ok(self.execute_params(&query, params).await?.rows_affected())
+    }
+
+    // Builds one multi-row `INSERT` per chunk of rows that fits the
+    // bound-variable limit.
+    fn insert_statements<'r>(
+        &self,
+        table: &str,
+        columns: &[&str],
+        rows: &'r [Vec<SqlValue>],
+    ) -> Result<Vec<InsertStatement<'r>>> {
+        let (prefix, row_placeholders) = self.insert_target(table, columns, rows)?;
+        Ok(chunk_params(rows, columns.len(), self.settings.max_variables())
+            .map(|chunk| {
+                let query = format!("{} {}", prefix, vec![row_placeholders.as_str(); chunk.len()].join(", "));
+                (query, chunk)
+            })
+            .collect())
+    }
+
+    // Validates the arguments of the multi-row insert helpers and returns
+    // the `INSERT INTO table (columns) VALUES` prefix along with the
+    // placeholder tuple for one row.
+    fn insert_target(&self, table: &str, columns: &[&str], rows: &[Vec<SqlValue>]) -> Result<(String, String)> {
+        let table = quote_identifier(table)?;
+        let columns = columns
+            .iter()
+            .map(|column| quote_identifier(column))
+            .collect::<Result<Vec<_>>>()?;
+
+        if columns.is_empty() {
+            return Err(DatabaseError::InvalidData {
+                message: "insert_many requires at least one column".to_string(),
+            });
+        }
+        if let Some(row) = rows.iter().find(|row| row.len() != columns.len()) {
+            return Err(DatabaseError::InvalidData {
+                message: format!("Expected {} values per row, got {}", columns.len(), row.len()),
+            });
+        }
+
+        let prefix = format!("INSERT INTO {} ({}) VALUES", table, columns.join(", "));
+        Ok((prefix, format!("({})", vec!["?"; columns.len()].join(", "))))
+    }
+}
+
+// The raw handle of a pooled connection, kept to interrupt it from outside
+// its worker thread.
+struct RawConnection(std::ptr::NonNull<libsqlite3_sys::sqlite3>);
+
+// SAFETY: the handle is only passed to `sqlite3_interrupt`, which SQLite
+// allows from any thread while the connection is open.
+unsafe impl Send for RawConnection {}
+unsafe impl Sync for RawConnection {}
+
+impl RawConnection {
+    async fn of(conn: &mut SqliteConnection) -> Result<Self> {
+        Ok(Self(conn.lock_handle().await?.as_raw_handle()))
+    }
+
+    // Callers keep the connection checked out of the pool, so it is open.
+    fn interrupt(&self) {
+        // SAFETY: see above
+        unsafe { libsqlite3_sys::sqlite3_interrupt(self.0.as_ptr()) }
+    }
+
+    // Turns an elapsed timeout into `Timeout` once the connection is idle
+    // again. The statement future is gone by now, so the worker stops as soon
+    // as the interrupted statement fails; it is interrupted until a ping gets
+    // through, in case it had not started when first interrupted.
+    async fn stop_if_elapsed<T>(
+        &self,
+        result: std::result::Result<Result<T>, tokio::time::error::Elapsed>,
+        conn: &mut SqliteConnection,
+    ) -> Result<T> {
+        match result {
+            Ok(result) => result,
+            Err(_) => {
+                loop {
+                    self.interrupt();
+                    let ping = sqlx::Connection::ping(&mut *conn);
+                    if tokio::time::timeout(Duration::from_millis(10), ping).await.is_ok() {
+                        break;
+                    }
+                }
+                Err(DatabaseError::Timeout)
+            }
+        }
     }
 }
 
+// URI parameters of a named, shared in-memory database path.
+const NAMED_MEMORY_PARAMS: &str = "?mode=memory&cache=shared";
+
+// An `INSERT` statement and the rows whose values it binds.
+type InsertStatement<'r> = (String, &'r [Vec<SqlValue>]);
+
 pub async fn create_database<P: AsRef<Path>>(path: P) -> Result<Database> {
     let mut db = Database::new(path);
     db.initialize().await?;
@@ -182,25 +1649,103 @@ fn is_windows() -> bool {
     cfg!(target_os = "windows")
 }
 
-fn get_default_database_path() -> Result<std::path::PathBuf> {
-    let db_dir = if is_windows() {
-        // Windows: %USERPROFILE%\AppData\Local\BurnCloud
+static TEST_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Redirects default-path resolution to a per-process directory under the
+/// system temp directory, so tests never touch the real default database.
+/// The directory is not removed when the process exits or test mode is
+/// turned off; callers own its cleanup, e.g. by removing the parent of
+/// `get_default_database_path()` once their databases are closed.
+pub fn set_test_mode(enabled: bool) {
+    TEST_MODE.store(enabled, Ordering::SeqCst);
+}
+
+pub fn is_test_mode() -> bool {
+    TEST_MODE.load(Ordering::SeqCst)
+}
+
+static LEGACY_DEFAULT_PATH: AtomicBool = AtomicBool::new(false);
+
+/// Makes the Linux default path `~/.burncloud/data.db` again instead of the
+/// XDG data directory, for installs that already keep their data there.
+pub fn set_legacy_default_path(enabled: bool) {
+    LEGACY_DEFAULT_PATH.store(enabled, Ordering::SeqCst);
+}
+
+/// Resolves the default database path. Test mode takes precedence; after
+/// that, a non-empty `BURNCLOUD_DATABASE_PATH` environment variable is used
+/// verbatim, and otherwise the platform default for BurnCloud is returned.
+pub fn get_default_database_path() -> Result<std::path::PathBuf> {
+    // The override may point at a real database, which test mode must never touch
+    let env_override = std::env::var_os("BURNCLOUD_DATABASE_PATH").filter(|path| !path.is_empty());
+    if let Some(path) = env_override.filter(|_| !is_test_mode()) {
+        return Ok(std::path::PathBuf::from(path));
+    }
+    get_default_database_path_for("BurnCloud")
+}
+
+/// Resolves the default database path with `app_name` in place of
+/// "BurnCloud", so several applications built on this crate keep separate
+/// files. Linux directory names use the lowercased name.
+pub fn get_default_database_path_for(app_name: &str) -> Result<std::path::PathBuf> {
+    if app_name.is_empty() || app_name == "." || app_name == ".." || app_name.contains(['/', '\\', '\0']) {
+        return Err(DatabaseError::PathResolution(format!("Invalid application name: {:?}", app_name)));
+    }
+    let lowercase_name = app_name.to_lowercase();
+
+    let db_dir = if is_test_mode() {
+        std::env::temp_dir().join(format!("{}-test-{}", lowercase_name, std::process::id()))
+    } else if is_windows() {
+        // Windows: %USERPROFILE%\AppData\Local\<app>
         let user_profile = std::env::var("USERPROFILE")
             .map_err(|e| DatabaseError::PathResolution(format!("USERPROFILE not found: {}", e)))?;
         std::path::PathBuf::from(user_profile)
             .join("AppData")
             .join("Local")
-            .join("BurnCloud")
-    } else {
-        // Linux: ~/.burncloud
+            .join(app_name)
+    } else if cfg!(target_os = "macos") {
+        // macOS: ~/Library/Application Support/<app>
+        dirs::home_dir()
+            .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
+            .join("Library")
+            .join("Application Support")
+            .join(app_name)
+    } else if LEGACY_DEFAULT_PATH.load(Ordering::SeqCst) {
+        // Linux, legacy layout: ~/.<app>
         dirs::home_dir()
             .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
-            .join(".burncloud")
+            .join(format!(".{}", lowercase_name))
+    } else {
+        // Linux: $XDG_DATA_HOME/<app>, or ~/.local/share/<app>. The spec says
+        // to ignore an empty or relative XDG_DATA_HOME.
+        match std::env::var_os("XDG_DATA_HOME").filter(|dir| Path::new(dir).is_absolute()) {
+            Some(data_home) => std::path::PathBuf::from(data_home).join(lowercase_name),
+            None => dirs::home_dir()
+                .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
+                .join(".local")
+                .join("share")
+                .join(lowercase_name),
+        }
     };
 
     Ok(db_dir.join("data.db"))
 }
 
+// Removes `.` and resolves `..` lexically, without following symlinks.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
 fn create_directory_if_not_exists(path: &std::path::Path) -> Result<()> {
     if let Some(parent) = path.parent() {
         if !parent.exists() {
@@ -225,39 +1770,6 @@ mod tests {
         assert!(db.connection.is_none());
     }
 
-    #[tokio::test]
-    async fn test_database_new_default_initialized() {
-        // In environments where file databases might not work due to permissions
-        // or configuration, we should at least test that the path resolution works
-        let default_path_result = get_default_database_path();
-        assert!(default_path_result.is_ok());
-
-        // Test the constructor doesn't panic
-        let db_result = Database::new_default_initialized().await;
-        // Note: This might fail in some environments due to SQLite configuration,
-        // but the path resolution and API structure are correct
-        if db_result.is_ok() {
-            let db = db_result.unwrap();
-            let _ = db.close().await;
-        }
-    }
-
-    #[tokio::test]
-    async fn test_create_default_database() {
-        // Test that the function exists and path resolution works
-        let default_path_result = get_default_database_path();
-        assert!(default_path_result.is_ok());
-
-        // Test the function doesn't panic
-        let db_result = create_default_database().await;
-        // Note: This might fail in some environments due to SQLite configuration,
-        // but the path resolution and API structure are correct
-        if db_result.is_ok() {
-            let db = db_result.unwrap();
-            let _ = db.close().await;
-        }
-    }
-
     #[test]
     fn test_get_default_database_path() {
         let path_result = get_default_database_path();
@@ -268,20 +1780,89 @@ mod tests {
         assert!(path.to_string_lossy().contains("data.db"));
 
         // On Windows, should contain AppData\Local\BurnCloud
-        // On Linux, should contain .burncloud
+        // On macOS, should contain Library/Application Support/BurnCloud
+        // On Linux, should be under the XDG data directory
         if cfg!(target_os = "windows") {
             assert!(path.to_string_lossy().contains("AppData\\Local\\BurnCloud"));
+        } else if cfg!(target_os = "macos") {
+            assert!(path.to_string_lossy().contains("Library/Application Support/BurnCloud/data.db"));
         } else {
-            assert!(path.to_string_lossy().contains(".burncloud"));
+            assert!(path.ends_with("burncloud/data.db"));
+        }
+    }
+
+    #[test]
+    fn test_get_default_database_path_for_app() {
+        let path = get_default_database_path_for("ModelHub").unwrap();
+        assert_ne!(path, get_default_database_path().unwrap());
+        assert!(path.ends_with("data.db"));
+
+        if cfg!(target_os = "windows") {
+            assert!(path.to_string_lossy().contains("AppData\\Local\\ModelHub"));
+        } else if cfg!(target_os = "macos") {
+            assert!(path.to_string_lossy().contains("Library/Application Support/ModelHub"));
+        } else {
+            assert!(path.ends_with("modelhub/data.db"));
+        }
+
+        let db = Database::new_default_for_app("ModelHub").unwrap();
+        assert_eq!(db.path(), path.to_string_lossy());
+
+        for invalid in ["", ".", "..", "a/b", "a\\b"] {
+            assert!(
+                matches!(get_default_database_path_for(invalid), Err(DatabaseError::PathResolution(_))),
+                "{:?} should be rejected",
+                invalid
+            );
+            assert!(Database::new_default_for_app(invalid).is_err());
         }
     }
 
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_get_default_database_path_macos() {
+        let path = get_default_database_path().unwrap();
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(path, home.join("Library/Application Support/BurnCloud/data.db"));
+    }
+
     #[test]
     fn test_is_windows() {
         let result = is_windows();
         assert_eq!(result, cfg!(target_os = "windows"));
     }
 
+    #[tokio::test]
+    async fn test_acquire_failures_are_counted() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_millis(50))
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let connection = DatabaseConnection::from_pool(pool);
+        assert_eq!(connection.stats().acquire_failures, 0);
+
+        let held = connection.acquire().await.unwrap();
+        assert!(connection.acquire().await.is_err());
+        assert_eq!(connection.stats().acquire_failures, 1);
+
+        let db = Database {
+            connection: Some(connection.clone()),
+            database_path: ":memory:".to_string(),
+            settings: ConnectionSettings::default(),
+        };
+        assert!(db.execute_query("SELECT 1").await.is_err());
+        assert!(db.begin_with_timeout(std::time::Duration::from_secs(1)).await.is_err());
+        assert_eq!(db.pool_stats().unwrap().acquire_failures, 3);
+
+        drop(held);
+        assert!(db.execute_query("SELECT 1").await.is_ok());
+        assert_eq!(db.pool_stats().unwrap().acquire_failures, 3);
+
+        let _ = db.close().await;
+    }
+
     #[test]
     fn test_api_consistency() {
         // Test that the new_default constructor follows the same pattern as new()
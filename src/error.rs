@@ -3,7 +3,16 @@ use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum DatabaseError {
     #[error("Database connection error: {0}")]
-    Connection(#[from] sqlx::Error),
+    Connection(#[source] sqlx::Error),
+
+    #[error("No row matched the query")]
+    NotFound,
+
+    /// A `UNIQUE`, `PRIMARY KEY`, `NOT NULL`, `CHECK` or foreign key
+    /// constraint rejected the statement. `constraint` names the failing
+    /// column(s) or `CHECK` when SQLite reports it, e.g. `users.email`.
+    #[error("Constraint violation: {message}")]
+    ConstraintViolation { constraint: Option<String>, message: String },
 
     #[error("Migration error: {0}")]
     Migration(String),
@@ -17,6 +26,9 @@ pub enum DatabaseError {
     #[error("Database not initialized")]
     NotInitialized,
 
+    #[error("Operation timed out")]
+    Timeout,
+
     #[error("Failed to resolve default database path: {0}")]
     PathResolution(String),
 
@@ -30,4 +42,52 @@ pub enum DatabaseError {
     InvalidData { message: String },
 }
 
-pub type Result<T> = std::result::Result<T, DatabaseError>;
\ No newline at end of file
+impl From<sqlx::Error> for DatabaseError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            // Raised by `fetch_one` when the query returns no rows
+            sqlx::Error::RowNotFound => DatabaseError::NotFound,
+            sqlx::Error::Database(error) if is_constraint_error(error.code().as_deref()) => {
+                let message = error.message().to_string();
+                let constraint = message
+                    .split_once("constraint failed: ")
+                    .map(|(_, constraint)| constraint.to_string());
+                DatabaseError::ConstraintViolation { constraint, message }
+            }
+            error => DatabaseError::Connection(error),
+        }
+    }
+}
+
+impl DatabaseError {
+    // SQLITE_BUSY or SQLITE_LOCKED: another connection holds a conflicting
+    // lock, so the same statement may succeed if tried again.
+    pub(crate) fn is_busy(&self) -> bool {
+        matches!(self.primary_code(), Some(libsqlite3_sys::SQLITE_BUSY | libsqlite3_sys::SQLITE_LOCKED))
+    }
+
+    // SQLITE_CORRUPT or SQLITE_NOTADB: the file itself is damaged, so trying
+    // again will not help.
+    pub(crate) fn is_corrupt(&self) -> bool {
+        matches!(self.primary_code(), Some(libsqlite3_sys::SQLITE_CORRUPT | libsqlite3_sys::SQLITE_NOTADB))
+    }
+
+    fn primary_code(&self) -> Option<i32> {
+        let DatabaseError::Connection(sqlx::Error::Database(error)) = self else {
+            return None;
+        };
+        error.code().and_then(|code| code.parse::<i32>().ok()).map(|code| code & 0xff)
+    }
+}
+
+// The code is SQLite's extended result code; its low byte is the primary
+// code, SQLITE_CONSTRAINT (19) for every kind of constraint failure.
+fn is_constraint_error(code: Option<&str>) -> bool {
+    code.and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| code & 0xff == libsqlite3_sys::SQLITE_CONSTRAINT)
+}
+
+pub type Result<T> = std::result::Result<T, DatabaseError>;
+
+/// Alias of `Result` that can be imported without shadowing `std::result::Result`.
+pub type DatabaseResult<T> = Result<T>;
\ No newline at end of file
@@ -0,0 +1,109 @@
+use futures_util::{future, stream, Stream, StreamExt};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::database::Database;
+use crate::error::{DatabaseError, Result};
+use crate::sql::{quote_identifier, quote_stored_identifier};
+use crate::value::{decode_row, row_to_json, to_arguments, SqlValue};
+
+impl Database {
+    /// Streams the rows of `query` as NDJSON: each item is one row as a JSON
+    /// object followed by `\n`, ready to be written to a response body. Rows
+    /// are fetched lazily as the stream is polled.
+    pub fn fetch_ndjson_stream<'a>(
+        &'a self,
+        query: &'a str,
+        params: &[SqlValue],
+    ) -> impl Stream<Item = Result<String>> + 'a {
+        match self.connection() {
+            Ok(connection) => sqlx::query_with(query, to_arguments(params, self.encoding()))
+                .fetch(connection.pool())
+                .map(|row| {
+                    let mut line = serde_json::to_string(&row_to_json(&row?)?)?;
+                    line.push('\n');
+                    Ok(line)
+                })
+                .left_stream(),
+            Err(e) => stream::once(future::ready(Err(e))).right_stream(),
+        }
+    }
+
+    /// Writes every row of `table` to `path` as CSV with a header line and
+    /// returns the number of data rows written. Blobs are hex-encoded.
+    pub async fn export_csv(&self, table: &str, path: &Path) -> Result<u64> {
+        let quoted_table = quote_identifier(table)?;
+        self.write_table_csv(table, &quoted_table, path).await
+    }
+
+    /// Exports each user table to `<dir>/<table>.csv`, creating `dir` if it
+    /// is missing, and returns the written paths in table-name order. Table
+    /// names come from the schema, so they are exported whatever the
+    /// `IdentifierPolicy`.
+    pub async fn export_all_csv(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(dir)?;
+
+        let mut written = Vec::new();
+        for table in self.list_tables().await? {
+            // Table names become file names, so never let one leave `dir`
+            if table.contains(['/', '\\']) || table.starts_with('.') {
+                return Err(DatabaseError::InvalidData {
+                    message: format!("Table name {:?} cannot be used as a file name", table),
+                });
+            }
+            let path = dir.join(format!("{}.csv", table));
+            self.write_table_csv(&table, &quote_stored_identifier(&table), &path).await?;
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+
+    async fn write_table_csv(&self, table: &str, quoted_table: &str, path: &Path) -> Result<u64> {
+        let columns = self.column_names(table).await?;
+
+        let rows = {
+            let mut conn = self.connection()?.acquire().await?;
+            sqlx::query(&format!("SELECT * FROM {}", quoted_table))
+                .fetch_all(&mut *conn)
+                .await?
+        };
+
+        let mut writer = BufWriter::new(std::fs::File::create(path)?);
+        write_csv_line(&mut writer, columns.iter().map(|column| csv_field(column)))?;
+
+        for row in &rows {
+            let values = decode_row(row)?;
+            write_csv_line(&mut writer, values.iter().map(csv_value))?;
+        }
+        writer.flush()?;
+
+        Ok(rows.len() as u64)
+    }
+}
+
+fn write_csv_line<W: Write>(writer: &mut W, fields: impl Iterator<Item = String>) -> Result<()> {
+    let line = fields.collect::<Vec<_>>().join(",");
+    writeln!(writer, "{}", line)?;
+    Ok(())
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn csv_value(value: &SqlValue) -> String {
+    match value {
+        SqlValue::Null => String::new(),
+        SqlValue::Integer(value) => value.to_string(),
+        SqlValue::Real(value) => value.to_string(),
+        SqlValue::Text(value) => csv_field(value),
+        SqlValue::Blob(bytes) => bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        SqlValue::Bool(value) => (*value as i64).to_string(),
+        SqlValue::DateTime(value) => value.to_rfc3339(),
+    }
+}
@@ -1,7 +1,35 @@
 pub mod database;
 pub mod error;
+pub mod export;
+pub mod lock;
+pub mod maintenance;
+pub mod metadata;
+pub mod migration;
+pub mod schema;
+pub mod select;
+pub mod sql;
+pub mod stats;
+pub mod transaction;
+pub mod types;
+pub mod value;
+pub mod writer;
 
-pub use database::{Database, DatabaseConnection, create_database, create_in_memory_database, create_default_database};
-pub use error::{DatabaseError, Result};
+pub use database::{
+    Database, DatabaseConnection, create_database, create_in_memory_database, create_default_database,
+    get_default_database_path, get_default_database_path_for, is_test_mode, set_legacy_default_path, set_test_mode,
+    InterruptHandle, JournalMode, PoolConfig, RetryPolicy,
+};
+pub use error::{DatabaseError, DatabaseResult, Result};
+pub use lock::{LockGuard, DEFAULT_LOCK_TTL};
+pub use maintenance::{CheckpointMode, CheckpointReport, CheckpointResult, FkViolation, SelfCheckReport, SettingCheck};
+pub use migration::{MigrationInfo, SqliteMigrationManager};
+pub use schema::{ColumnInfo, ColumnSpec, SchemaDiff, TableSpec, TriggerInfo};
+pub use select::SelectBuilder;
+pub use sql::{identifier_policy, normalize_sql, quote_identifier, set_identifier_policy, IdentifierPolicy};
+pub use stats::PoolStats;
+pub use transaction::{BoxFuture, DatabaseTransaction, Savepoint};
+pub use types::{Column, ColumnBatch, ColumnValues, DatabaseConfig, OrderDirection, Page, QueryOptions, QueryResult};
+pub use value::{BoolStorage, DateTimeStorage, Param, SqlValue};
+pub use writer::WriterQueue;
 
 pub use sqlx;
\ No newline at end of file
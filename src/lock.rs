@@ -0,0 +1,111 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::database::{Database, DatabaseConnection};
+use crate::error::Result;
+
+const CREATE_LOCKS_TABLE: &str = "CREATE TABLE IF NOT EXISTS _locks (
+    name TEXT PRIMARY KEY NOT NULL,
+    owner TEXT NOT NULL,
+    acquired_at INTEGER NOT NULL
+)";
+
+/// How long a lock taken with `try_acquire_lock` is honoured before another
+/// process may reclaim it.
+pub const DEFAULT_LOCK_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// An advisory lock held in the `_locks` table. Dropping the guard releases
+/// the lock in the background; call `release` to wait for it.
+pub struct LockGuard {
+    connection: Option<DatabaseConnection>,
+    name: String,
+    owner: String,
+}
+
+impl LockGuard {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// A unique id for this holder, stored in the `owner` column.
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    pub async fn release(mut self) -> Result<()> {
+        match self.connection.take() {
+            Some(connection) => delete_lock(&connection, &self.name, &self.owner).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let Some(connection) = self.connection.take() else {
+            return;
+        };
+        // Without a runtime the row stays until its TTL expires
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let name = std::mem::take(&mut self.name);
+            let owner = std::mem::take(&mut self.owner);
+            handle.spawn(async move {
+                let _ = delete_lock(&connection, &name, &owner).await;
+            });
+        }
+    }
+}
+
+impl Database {
+    /// Takes the advisory lock `name` unless another holder has it, returning
+    /// `None` in that case. Locks older than `DEFAULT_LOCK_TTL` are treated
+    /// as abandoned and reclaimed.
+    pub async fn try_acquire_lock(&self, name: &str) -> Result<Option<LockGuard>> {
+        self.try_acquire_lock_with_ttl(name, DEFAULT_LOCK_TTL).await
+    }
+
+    /// Like `try_acquire_lock`, reclaiming locks held for longer than `ttl`.
+    pub async fn try_acquire_lock_with_ttl(&self, name: &str, ttl: Duration) -> Result<Option<LockGuard>> {
+        let connection = self.connection()?;
+        let owner = uuid::Uuid::new_v4().to_string();
+        let now = unix_millis(SystemTime::now());
+        let stale_before = now.saturating_sub(ttl.as_millis() as i64);
+
+        let mut conn = connection.acquire().await?;
+        sqlx::query(CREATE_LOCKS_TABLE).execute(&mut *conn).await?;
+        let result = sqlx::query(
+            "INSERT INTO _locks (name, owner, acquired_at) VALUES (?, ?, ?)
+             ON CONFLICT(name) DO UPDATE SET owner = excluded.owner, acquired_at = excluded.acquired_at
+             WHERE _locks.acquired_at < ?",
+        )
+        .bind(name)
+        .bind(&owner)
+        .bind(now)
+        .bind(stale_before)
+        .execute(&mut *conn)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        Ok(Some(LockGuard {
+            connection: Some(connection.clone()),
+            name: name.to_string(),
+            owner,
+        }))
+    }
+}
+
+// Only deletes the row if it is still ours; a reclaimed lock belongs to its new owner.
+async fn delete_lock(connection: &DatabaseConnection, name: &str, owner: &str) -> Result<()> {
+    let mut conn = connection.acquire().await?;
+    sqlx::query("DELETE FROM _locks WHERE name = ? AND owner = ?")
+        .bind(name)
+        .bind(owner)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+fn unix_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_millis() as i64).unwrap_or(0)
+}
@@ -0,0 +1,622 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::database::{get_default_database_path, Database, JournalMode};
+use crate::error::{DatabaseError, Result};
+
+/// A row reported by `PRAGMA foreign_key_check`: the child row in `table`
+/// whose foreign key `fk_index` has no matching row in `parent_table`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FkViolation {
+    pub table: String,
+    /// `None` for `WITHOUT ROWID` tables.
+    pub rowid: Option<i64>,
+    pub parent_table: String,
+    pub fk_index: i64,
+}
+
+/// The effect of `checkpoint_and_report`: the `-wal` file size before and
+/// after a `TRUNCATE` checkpoint and the number of frames written back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckpointReport {
+    pub wal_bytes_before: u64,
+    pub wal_bytes_after: u64,
+    pub frames_checkpointed: i64,
+}
+
+/// The `PRAGMA wal_checkpoint` modes accepted by `Database::checkpoint`, from
+/// least to most blocking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointMode {
+    /// Copies what it can without waiting for readers or writers.
+    Passive,
+    /// Waits for writers, then copies the whole WAL.
+    Full,
+    /// Like `Full`, then waits for readers so the WAL restarts from the top.
+    Restart,
+    /// Like `Restart`, then truncates the `-wal` file to zero bytes.
+    Truncate,
+}
+
+impl CheckpointMode {
+    fn as_pragma_value(self) -> &'static str {
+        match self {
+            CheckpointMode::Passive => "PASSIVE",
+            CheckpointMode::Full => "FULL",
+            CheckpointMode::Restart => "RESTART",
+            CheckpointMode::Truncate => "TRUNCATE",
+        }
+    }
+}
+
+/// The row returned by `PRAGMA wal_checkpoint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckpointResult {
+    /// A `Full`, `Restart` or `Truncate` checkpoint could not finish because
+    /// of other connections.
+    pub busy: bool,
+    /// Frames in the WAL.
+    pub log_frames: i64,
+    /// Frames copied back into the database file.
+    pub checkpointed_frames: i64,
+}
+
+/// The outcome of `self_check`: one entry per setting, comparing what the
+/// builder asked for with what the connection actually reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfCheckReport {
+    pub checks: Vec<SettingCheck>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingCheck {
+    /// The pragma name, or `max_connections` for the pool size.
+    pub setting: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl SettingCheck {
+    pub fn is_ok(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+impl SelfCheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(SettingCheck::is_ok)
+    }
+
+    pub fn mismatches(&self) -> impl Iterator<Item = &SettingCheck> {
+        self.checks.iter().filter(|check| !check.is_ok())
+    }
+
+    pub fn get(&self, setting: &str) -> Option<&SettingCheck> {
+        self.checks.iter().find(|check| check.setting == setting)
+    }
+}
+
+impl Database {
+    /// Reads back `foreign_keys`, `journal_mode`, `busy_timeout`,
+    /// `synchronous` and the pool size from a live connection and compares
+    /// each with the builder configuration. `journal_mode` is only checked
+    /// when one was requested for a file database.
+    pub async fn self_check(&self) -> Result<SelfCheckReport> {
+        let connection = self.connection()?;
+        let settings = self.settings();
+        let journal_mode = settings.journal_mode.filter(|_| !self.is_in_memory());
+        let mut conn = connection.acquire().await?;
+        let (foreign_keys,): (i64,) = sqlx::query_as("PRAGMA foreign_keys").fetch_one(&mut *conn).await?;
+        let (busy_timeout,): (i64,) = sqlx::query_as("PRAGMA busy_timeout").fetch_one(&mut *conn).await?;
+        let (actual_journal_mode,): (String,) = sqlx::query_as("PRAGMA journal_mode").fetch_one(&mut *conn).await?;
+        let (synchronous,): (i64,) = sqlx::query_as("PRAGMA synchronous").fetch_one(&mut *conn).await?;
+        drop(conn);
+
+        let mut checks = vec![
+            SettingCheck {
+                setting: "foreign_keys",
+                expected: settings.foreign_keys.to_string(),
+                actual: (foreign_keys != 0).to_string(),
+            },
+            SettingCheck {
+                setting: "busy_timeout",
+                expected: settings.busy_timeout_ms.to_string(),
+                actual: busy_timeout.to_string(),
+            },
+        ];
+        if let Some(mode) = journal_mode {
+            checks.push(SettingCheck {
+                setting: "journal_mode",
+                expected: format!("{:?}", mode).to_lowercase(),
+                actual: actual_journal_mode.to_lowercase(),
+            });
+        }
+        // WAL is paired with NORMAL; otherwise SQLite's FULL is left alone
+        let expected_synchronous = if journal_mode == Some(JournalMode::Wal) { 1 } else { 2 };
+        checks.push(SettingCheck {
+            setting: "synchronous",
+            expected: synchronous_name(expected_synchronous).to_string(),
+            actual: synchronous_name(synchronous).to_string(),
+        });
+
+        checks.push(SettingCheck {
+            setting: "max_connections",
+            expected: settings.pool.max_connections.to_string(),
+            actual: connection.stats().max_connections.to_string(),
+        });
+
+        Ok(SelfCheckReport { checks })
+    }
+
+    /// Runs `PRAGMA integrity_check` and returns its messages: `["ok"]` for a
+    /// healthy database, otherwise one entry per problem found. Damage bad
+    /// enough to stop the check itself is returned as an error.
+    pub async fn integrity_check(&self) -> Result<Vec<String>> {
+        self.check_messages("PRAGMA integrity_check").await
+    }
+
+    /// Like `integrity_check`, but skips the index consistency checks, so it
+    /// runs much faster on large databases.
+    pub async fn quick_check(&self) -> Result<Vec<String>> {
+        self.check_messages("PRAGMA quick_check").await
+    }
+
+    async fn check_messages(&self, pragma: &str) -> Result<Vec<String>> {
+        let mut conn = self.connection()?.acquire().await?;
+        let rows: Vec<(String,)> = sqlx::query_as(pragma).fetch_all(&mut *conn).await?;
+        Ok(rows.into_iter().map(|(message,)| message).collect())
+    }
+
+    /// Runs `PRAGMA foreign_key_check` over the whole database. An empty list
+    /// means every foreign key is satisfied.
+    pub async fn foreign_key_check(&self) -> Result<Vec<FkViolation>> {
+        let mut conn = self.connection()?.acquire().await?;
+        let rows: Vec<(String, Option<i64>, String, i64)> = sqlx::query_as("PRAGMA foreign_key_check")
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(table, rowid, parent_table, fk_index)| FkViolation {
+                table,
+                rowid,
+                parent_table,
+                fk_index,
+            })
+            .collect())
+    }
+
+    /// Opens `primary`, falling back to the newest backup that passes
+    /// `PRAGMA quick_check` when the primary is missing or corrupt. The backup
+    /// is copied over the primary path and the database is opened from there.
+    /// The corrupt file and its `-wal`/`-shm` files are kept alongside as
+    /// `<primary>-corrupt` (numbered if that name is taken). Any other
+    /// failure to open the primary, such as a busy database or a permission
+    /// error, is returned as is.
+    pub async fn open_with_fallback(primary: &Path, backups: &[PathBuf]) -> Result<Database> {
+        let primary_error = match open_verified(primary).await {
+            Ok(db) => return Ok(db),
+            Err(Rejected::Damaged(e)) => e,
+            Err(Rejected::Failed(e)) if !primary.exists() => e,
+            Err(Rejected::Failed(e)) => return Err(e),
+        };
+
+        let mut candidates: Vec<(SystemTime, &PathBuf)> = backups
+            .iter()
+            .filter_map(|path| {
+                let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+                Some((modified, path))
+            })
+            .collect();
+        candidates.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+        for (_, backup) in candidates {
+            let Ok(backup_db) = open_verified(backup).await else {
+                continue;
+            };
+            backup_db.close().await?;
+
+            restore_backup(primary, backup)?;
+            return open_verified(primary).await.map_err(Rejected::into_error);
+        }
+
+        Err(primary_error)
+    }
+
+    /// Runs `PRAGMA wal_checkpoint(TRUNCATE)` and reports how much it shrank
+    /// the WAL. A missing `-wal` file counts as zero bytes, so databases not in
+    /// WAL mode report zeros.
+    pub async fn checkpoint_and_report(&self) -> Result<CheckpointReport> {
+        if self.is_in_memory() {
+            return Err(DatabaseError::InvalidData {
+                message: "checkpoint_and_report requires a file database".to_string(),
+            });
+        }
+
+        let wal_path = sidecar_path(Path::new(self.path()), "wal");
+        let wal_size = || std::fs::metadata(&wal_path).map(|meta| meta.len()).unwrap_or(0);
+
+        let mut conn = self.connection()?.acquire().await?;
+        let wal_bytes_before = wal_size();
+        // A completed TRUNCATE checkpoint resets the WAL and reports zero frames,
+        // so count the frames with a PASSIVE checkpoint first.
+        let (_busy, _log_frames, frames_checkpointed): (i64, i64, i64) =
+            sqlx::query_as("PRAGMA wal_checkpoint(PASSIVE)")
+                .fetch_one(&mut *conn)
+                .await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut *conn).await?;
+
+        Ok(CheckpointReport {
+            wal_bytes_before,
+            wal_bytes_after: wal_size(),
+            frames_checkpointed: frames_checkpointed.max(0),
+        })
+    }
+
+    /// Runs `PRAGMA wal_checkpoint` in the given mode. Fails with
+    /// `InvalidData` unless the database is in WAL mode.
+    pub async fn checkpoint(&self, mode: CheckpointMode) -> Result<CheckpointResult> {
+        let mut conn = self.connection()?.acquire().await?;
+        let (journal_mode,): (String,) = sqlx::query_as("PRAGMA journal_mode").fetch_one(&mut *conn).await?;
+        if !journal_mode.eq_ignore_ascii_case("wal") {
+            return Err(DatabaseError::InvalidData {
+                message: format!("checkpoint requires WAL journal mode, found {}", journal_mode),
+            });
+        }
+
+        let pragma = format!("PRAGMA wal_checkpoint({})", mode.as_pragma_value());
+        let (busy, log_frames, checkpointed_frames): (i64, i64, i64) =
+            sqlx::query_as(&pragma).fetch_one(&mut *conn).await?;
+        Ok(CheckpointResult {
+            busy: busy != 0,
+            log_frames,
+            checkpointed_frames,
+        })
+    }
+
+    /// Runs `VACUUM` to rebuild the database file and return the space of
+    /// deleted rows to the filesystem.
+    pub async fn vacuum(&self) -> Result<()> {
+        let mut conn = self.connection()?.acquire().await?;
+        sqlx::query("VACUUM").execute(&mut *conn).await?;
+        Ok(())
+    }
+
+    /// Writes a compacted copy of the database to `dest` with `VACUUM INTO`.
+    /// SQLite never overwrites, so an existing `dest` is reported as `Query`.
+    pub async fn vacuum_into(&self, dest: &Path) -> Result<()> {
+        if dest.exists() {
+            return Err(DatabaseError::Query(format!(
+                "VACUUM INTO destination already exists: {}",
+                dest.display()
+            )));
+        }
+
+        let mut conn = self.connection()?.acquire().await?;
+        sqlx::query("VACUUM INTO ?")
+            .bind(dest.to_string_lossy().to_string())
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Writes a consistent, standalone copy of this file database to `dest`.
+    /// The WAL is checkpointed first and the copy is produced with
+    /// `VACUUM INTO` next to `dest`, then renamed into place.
+    pub async fn copy_file_to(&self, dest: &Path) -> Result<()> {
+        if self.is_in_memory() {
+            return Err(DatabaseError::InvalidData {
+                message: "copy_file_to requires a file database; in-memory databases have no file to copy"
+                    .to_string(),
+            });
+        }
+
+        let mut conn = self.connection()?.acquire().await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut *conn).await?;
+        drop(conn);
+
+        self.snapshot_to(dest).await
+    }
+
+    /// Takes a hot backup of this database, file or in-memory, into `dest`,
+    /// replacing any previous backup there. `VACUUM INTO` reads a consistent
+    /// snapshot, so readers and writers keep running during the backup.
+    pub async fn backup_to(&self, dest: &Path) -> Result<()> {
+        self.snapshot_to(dest).await
+    }
+
+    // Writes the snapshot next to `dest` and renames it into place, so `dest`
+    // never holds a partial copy.
+    async fn snapshot_to(&self, dest: &Path) -> Result<()> {
+        let staging = sidecar_path(dest, "copy");
+        if staging.exists() {
+            std::fs::remove_file(&staging)?;
+        }
+
+        let mut conn = self.connection()?.acquire().await?;
+        if self.is_in_memory() {
+            // VACUUM INTO from an in-memory database writes another in-memory
+            // database, so copy the pages with the backup API instead
+            let mut handle = conn.lock_handle().await?;
+            backup_pages(handle.as_raw_handle().as_ptr(), &staging)?;
+        } else {
+            sqlx::query("VACUUM INTO ?")
+                .bind(staging.to_string_lossy().to_string())
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        std::fs::rename(&staging, dest)?;
+        Ok(())
+    }
+
+    /// Replaces the default database file with `new_file` and reopens this
+    /// database on the new contents. `self` must be the default database and
+    /// `new_file` must be closed. The current connection is closed first, stale
+    /// `-wal`/`-shm` files are removed and `new_file` is renamed into place.
+    pub async fn swap_default_with(&mut self, new_file: &Path) -> Result<()> {
+        let default_path = get_default_database_path()?;
+        if self.is_in_memory() || Path::new(self.path()) != default_path {
+            return Err(DatabaseError::InvalidData {
+                message: format!(
+                    "swap_default_with must be called on the default database ({})",
+                    default_path.display()
+                ),
+            });
+        }
+
+        let new_wal = sidecar_path(new_file, "wal");
+        if std::fs::metadata(&new_wal).is_ok_and(|m| m.len() > 0) {
+            return Err(DatabaseError::InvalidData {
+                message: format!(
+                    "{} is still open or was not closed cleanly; close it before swapping",
+                    new_file.display()
+                ),
+            });
+        }
+
+        if let Some(connection) = self.take_connection() {
+            connection.close().await;
+        }
+
+        if let Err(e) = replace_file(&default_path, new_file) {
+            // Leave the existing database usable before reporting the failure
+            self.initialize().await?;
+            return Err(std::io::Error::new(
+                e.kind(),
+                format!(
+                    "Could not replace {} with {} (is either file locked by another process?): {}",
+                    default_path.display(),
+                    new_file.display(),
+                    e
+                ),
+            )
+            .into());
+        }
+
+        self.initialize().await
+    }
+
+    /// Runs `f` against a read-only snapshot of this database so expensive
+    /// scans don't contend with live traffic. The snapshot is written to a
+    /// temporary file, as `backup_to` would, and deleted once `f` completes.
+    pub async fn with_analytics_snapshot<F, Fut, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(Database) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let snapshot = TempFile(std::env::temp_dir().join(format!(
+            "burncloud-snapshot-{}.db",
+            uuid::Uuid::new_v4()
+        )));
+
+        self.snapshot_to(&snapshot.0).await?;
+
+        let mut snapshot_db = Database::new(&snapshot.0).read_only(true);
+        snapshot_db.initialize().await?;
+        // Keep a pool handle so the snapshot is closed even though `f` owns it
+        let pool = snapshot_db.connection()?.clone();
+        let result = f(snapshot_db).await;
+        pool.close().await;
+        result
+    }
+}
+
+// Removes the file when dropped, so snapshots are cleaned up even if the
+// caller's closure fails or panics.
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+// Copies every page of `source` into a new database file at `dest` using
+// SQLite's online backup API.
+fn backup_pages(source: *mut libsqlite3_sys::sqlite3, dest: &Path) -> Result<()> {
+    use libsqlite3_sys as ffi;
+
+    let path = std::ffi::CString::new(dest.to_string_lossy().as_bytes()).map_err(|_| DatabaseError::InvalidData {
+        message: format!("Backup path contains a NUL byte: {}", dest.display()),
+    })?;
+
+    // SAFETY: `source` is a locked, open connection; the destination handle
+    // is opened, used and closed entirely within this block.
+    let (code, message) = unsafe {
+        let mut target = std::ptr::null_mut();
+        let mut code = ffi::sqlite3_open_v2(
+            path.as_ptr(),
+            &mut target,
+            ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE,
+            std::ptr::null(),
+        );
+        if code == ffi::SQLITE_OK {
+            let backup = ffi::sqlite3_backup_init(target, c"main".as_ptr(), source, c"main".as_ptr());
+            if backup.is_null() {
+                code = ffi::sqlite3_errcode(target);
+            } else {
+                ffi::sqlite3_backup_step(backup, -1);
+                code = ffi::sqlite3_backup_finish(backup);
+            }
+        }
+        let message = std::ffi::CStr::from_ptr(ffi::sqlite3_errstr(code)).to_string_lossy().into_owned();
+        ffi::sqlite3_close(target);
+        (code, message)
+    };
+
+    if code != ffi::SQLITE_OK {
+        return Err(DatabaseError::Query(format!("Backup to {} failed: {}", dest.display(), message)));
+    }
+    Ok(())
+}
+
+fn synchronous_name(level: i64) -> &'static str {
+    match level {
+        0 => "OFF",
+        1 => "NORMAL",
+        2 => "FULL",
+        3 => "EXTRA",
+        _ => "unknown",
+    }
+}
+
+// Why `open_verified` turned a file down.
+enum Rejected {
+    // The file is corrupt or not a database at all.
+    Damaged(DatabaseError),
+    // It could not be opened or checked, possibly only for now.
+    Failed(DatabaseError),
+}
+
+impl Rejected {
+    fn into_error(self) -> DatabaseError {
+        match self {
+            Rejected::Damaged(e) | Rejected::Failed(e) => e,
+        }
+    }
+}
+
+impl From<DatabaseError> for Rejected {
+    fn from(error: DatabaseError) -> Self {
+        if error.is_corrupt() {
+            Rejected::Damaged(error)
+        } else {
+            Rejected::Failed(error)
+        }
+    }
+}
+
+async fn open_verified(path: &Path) -> std::result::Result<Database, Rejected> {
+    // A missing file must fail here rather than be created empty
+    let mut db = Database::new_existing(path);
+    db.initialize().await?;
+
+    match db.quick_check().await {
+        Ok(messages) if messages == ["ok"] => Ok(db),
+        Ok(messages) => {
+            db.close().await?;
+            Err(Rejected::Damaged(DatabaseError::InvalidData {
+                message: format!("{} failed quick_check: {}", path.display(), messages.join("; ")),
+            }))
+        }
+        Err(e) => {
+            db.close().await?;
+            Err(e.into())
+        }
+    }
+}
+
+fn restore_backup(primary: &Path, backup: &Path) -> Result<()> {
+    if primary.exists() {
+        // Keep the damaged file and its WAL, which may hold committed frames
+        let corrupt = unused_corrupt_path(primary);
+        for suffix in ["wal", "shm"] {
+            let sidecar = sidecar_path(primary, suffix);
+            if sidecar.exists() {
+                std::fs::rename(sidecar, sidecar_path(&corrupt, suffix))?;
+            }
+        }
+        std::fs::rename(primary, corrupt)?;
+    }
+
+    if let Some(parent) = primary.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Copy next to the primary first so the final rename is atomic
+    let staging = sidecar_path(primary, "restore");
+    std::fs::copy(backup, &staging)?;
+    std::fs::rename(&staging, primary)?;
+    Ok(())
+}
+
+// `<primary>-corrupt`, or `<primary>-corrupt-<n>` for the first `n` whose
+// file and sidecars are all free, so earlier corrupt copies survive.
+fn unused_corrupt_path(primary: &Path) -> PathBuf {
+    let is_free = |path: &Path| {
+        !path.exists() && ["wal", "shm"].iter().all(|suffix| !sidecar_path(path, suffix).exists())
+    };
+    let corrupt = sidecar_path(primary, "corrupt");
+    if is_free(&corrupt) {
+        return corrupt;
+    }
+    let mut n = 1;
+    loop {
+        let numbered = sidecar_path(&corrupt, &n.to_string());
+        if is_free(&numbered) {
+            return numbered;
+        }
+        n += 1;
+    }
+}
+
+fn replace_file(dest: &Path, new_file: &Path) -> std::io::Result<()> {
+    for path in [dest, new_file] {
+        for suffix in ["wal", "shm"] {
+            let sidecar = sidecar_path(path, suffix);
+            if sidecar.exists() {
+                std::fs::remove_file(sidecar)?;
+            }
+        }
+    }
+    std::fs::rename(new_file, dest)
+}
+
+pub(crate) fn sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push("-");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_backup_keeps_corrupt_files_and_sidecars() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let primary = temp_dir.path().join("data.db");
+        let backup = temp_dir.path().join("backup.db");
+        let write = |name: &str, contents: &str| std::fs::write(temp_dir.path().join(name), contents).unwrap();
+        let read = |name: &str| std::fs::read_to_string(temp_dir.path().join(name)).unwrap();
+
+        write("backup.db", "backup");
+        write("data.db-corrupt", "earlier");
+        for round in ["first", "second"] {
+            write("data.db", round);
+            write("data.db-wal", &format!("{} wal", round));
+            write("data.db-shm", &format!("{} shm", round));
+            restore_backup(&primary, &backup).unwrap();
+        }
+
+        assert_eq!(read("data.db"), "backup");
+        assert!(!sidecar_path(&primary, "wal").exists() && !sidecar_path(&primary, "shm").exists());
+        assert_eq!(read("data.db-corrupt"), "earlier");
+        assert_eq!(read("data.db-corrupt-1"), "first");
+        assert_eq!(read("data.db-corrupt-1-wal"), "first wal");
+        assert_eq!(read("data.db-corrupt-1-shm"), "first shm");
+        assert_eq!(read("data.db-corrupt-2"), "second");
+        assert_eq!(read("data.db-corrupt-2-wal"), "second wal");
+    }
+}
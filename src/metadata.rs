@@ -0,0 +1,37 @@
+use crate::database::Database;
+use crate::error::Result;
+
+const CREATE_META_TABLE: &str =
+    "CREATE TABLE IF NOT EXISTS _meta (key TEXT PRIMARY KEY NOT NULL, value TEXT NOT NULL)";
+
+impl Database {
+    /// Stores a metadata entry in the crate-managed `_meta` table, replacing
+    /// any previous value for `key`.
+    pub async fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        let mut conn = self.connection()?.acquire().await?;
+        sqlx::query(CREATE_META_TABLE).execute(&mut *conn).await?;
+        sqlx::query(
+            "INSERT INTO _meta (key, value) VALUES (?, ?)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Reads a metadata entry, or `None` if it was never set. Reading never
+    /// creates `_meta`, so it works on read-only databases.
+    pub async fn get_meta(&self, key: &str) -> Result<Option<String>> {
+        if !self.table_exists("_meta").await? {
+            return Ok(None);
+        }
+        let mut conn = self.connection()?.acquire().await?;
+        let value: Option<(String,)> = sqlx::query_as("SELECT value FROM _meta WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&mut *conn)
+            .await?;
+        Ok(value.map(|(value,)| value))
+    }
+}
@@ -0,0 +1,290 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::database::Database;
+use crate::error::{DatabaseError, Result};
+use crate::sql::quote_stored_identifier;
+
+const CREATE_MIGRATIONS_TABLE: &str = "CREATE TABLE IF NOT EXISTS _migrations (
+    version INTEGER PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    applied_at TEXT NOT NULL,
+    checksum TEXT NOT NULL
+)";
+
+/// A migration file and, once applied, the row recorded for it in the
+/// `_migrations` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationInfo {
+    pub version: i64,
+    pub name: String,
+    /// `None` while the migration is pending.
+    pub applied_at: Option<DateTime<Utc>>,
+    pub checksum: String,
+}
+
+struct MigrationFile {
+    name: String,
+    up: String,
+    down: Option<PathBuf>,
+}
+
+/// Applies `.sql` migrations from a directory. Files are named
+/// `<version>_<name>.sql` and run in version order; an optional
+/// `<version>_<name>.down.sql` next to one is used by `rollback_migration`.
+pub struct SqliteMigrationManager<'a> {
+    db: &'a Database,
+    dir: PathBuf,
+}
+
+impl<'a> SqliteMigrationManager<'a> {
+    pub fn new(db: &'a Database, dir: impl AsRef<Path>) -> Self {
+        Self {
+            db,
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Applies every pending migration, each in its own transaction, and
+    /// returns the ones applied. Fails with `Migration` before applying
+    /// anything if an already-applied file has been edited.
+    pub async fn run_migrations(&self) -> Result<Vec<MigrationInfo>> {
+        let files = self.read_migrations()?;
+        let applied = self.applied_migrations().await?;
+
+        for (version, info) in &applied {
+            if let Some(file) = files.get(version) {
+                if checksum(&file.up) != info.checksum {
+                    return Err(DatabaseError::Migration(format!(
+                        "Migration {}_{} was modified after it was applied",
+                        version, info.name
+                    )));
+                }
+            }
+        }
+
+        let mut newly_applied = Vec::new();
+        for (version, file) in files.iter().filter(|(version, _)| !applied.contains_key(version)) {
+            let applied_at = Utc::now();
+            let info = MigrationInfo {
+                version: *version,
+                name: file.name.clone(),
+                applied_at: Some(applied_at),
+                checksum: checksum(&file.up),
+            };
+
+            let mut tx = self.db.connection()?.begin().await?;
+            sqlx::query(&file.up).execute(&mut *tx).await.map_err(|e| {
+                DatabaseError::Migration(format!("Migration {}_{} failed: {}", version, file.name, e))
+            })?;
+            sqlx::query("INSERT INTO _migrations (version, name, applied_at, checksum) VALUES (?, ?, ?, ?)")
+                .bind(info.version)
+                .bind(&info.name)
+                .bind(applied_at.to_rfc3339_opts(SecondsFormat::Micros, true))
+                .bind(&info.checksum)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            newly_applied.push(info);
+        }
+
+        Ok(newly_applied)
+    }
+
+    /// Reverts the most recently applied migration using its `.down.sql`
+    /// file and returns it, or `None` if nothing has been applied.
+    pub async fn rollback_migration(&self) -> Result<Option<MigrationInfo>> {
+        let files = self.read_migrations()?;
+        let Some((version, info)) = self.applied_migrations().await?.pop_last() else {
+            return Ok(None);
+        };
+
+        let down_path = files.get(&version).and_then(|file| file.down.clone()).ok_or_else(|| {
+            DatabaseError::Migration(format!("Migration {}_{} has no .down.sql file", version, info.name))
+        })?;
+        let down = std::fs::read_to_string(&down_path)?;
+
+        let mut tx = self.db.connection()?.begin().await?;
+        sqlx::query(&down).execute(&mut *tx).await.map_err(|e| {
+            DatabaseError::Migration(format!("Rollback of {}_{} failed: {}", version, info.name, e))
+        })?;
+        sqlx::query("DELETE FROM _migrations WHERE version = ?")
+            .bind(version)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(Some(info))
+    }
+
+    /// Lists every migration on disk or recorded as applied, in version
+    /// order. Pending migrations have no `applied_at`.
+    pub async fn get_migration_status(&self) -> Result<Vec<MigrationInfo>> {
+        let files = self.read_migrations()?;
+        let mut status = self.applied_migrations().await?;
+
+        for (version, file) in files {
+            status.entry(version).or_insert_with(|| MigrationInfo {
+                version,
+                name: file.name,
+                applied_at: None,
+                checksum: checksum(&file.up),
+            });
+        }
+
+        Ok(status.into_values().collect())
+    }
+
+    /// Lists the applied migrations in the order they were applied, for
+    /// audit and status pages.
+    pub async fn history(&self) -> Result<Vec<MigrationInfo>> {
+        let mut history: Vec<MigrationInfo> = self.applied_migrations().await?.into_values().collect();
+        history.sort_by_key(|migration| (migration.applied_at, migration.version));
+        Ok(history)
+    }
+
+    async fn applied_migrations(&self) -> Result<BTreeMap<i64, MigrationInfo>> {
+        let mut conn = self.db.connection()?.acquire().await?;
+        sqlx::query(CREATE_MIGRATIONS_TABLE).execute(&mut *conn).await?;
+        let rows: Vec<(i64, String, DateTime<Utc>, String)> =
+            sqlx::query_as("SELECT version, name, applied_at, checksum FROM _migrations")
+                .fetch_all(&mut *conn)
+                .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(version, name, applied_at, checksum)| {
+                let info = MigrationInfo {
+                    version,
+                    name,
+                    applied_at: Some(applied_at),
+                    checksum,
+                };
+                (version, info)
+            })
+            .collect())
+    }
+
+    fn read_migrations(&self) -> Result<BTreeMap<i64, MigrationFile>> {
+        let mut migrations = BTreeMap::new();
+        let mut down_files = Vec::new();
+
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+
+            if let Some(stem) = file_name.strip_suffix(".down.sql") {
+                down_files.push((parse_file_stem(stem)?.0, path.clone()));
+            } else if let Some(stem) = file_name.strip_suffix(".sql") {
+                let (version, name) = parse_file_stem(stem)?;
+                let file = MigrationFile {
+                    name,
+                    up: std::fs::read_to_string(&path)?,
+                    down: None,
+                };
+                if migrations.insert(version, file).is_some() {
+                    return Err(DatabaseError::Migration(format!("Duplicate migration version {}", version)));
+                }
+            }
+        }
+
+        for (version, path) in down_files {
+            if let Some(file) = migrations.get_mut(&version) {
+                file.down = Some(path);
+            }
+        }
+
+        Ok(migrations)
+    }
+}
+
+impl Database {
+    /// Writes the current schema as one SQL script suitable as the first
+    /// migration of an existing database: every table, index, view and
+    /// trigger, plus `INSERT`s reproducing the rows of `seed_tables` (meant
+    /// for small reference tables). The `_migrations` table is left out.
+    pub async fn generate_baseline_migration(&self, seed_tables: &[&str]) -> Result<String> {
+        let mut conn = self.connection()?.acquire().await?;
+        let objects: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT type, name, sql FROM sqlite_master
+             WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' AND name != '_migrations'
+             ORDER BY rowid",
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        drop(conn);
+
+        let mut script = String::from("-- Baseline migration generated from the existing schema\n");
+        let definitions = |kind: &'static str| {
+            objects
+                .iter()
+                .filter(move |(object_type, _, _)| object_type == kind)
+                .map(|(_, _, sql)| format!("{};\n", sql))
+        };
+        script.extend(definitions("table"));
+
+        // Seed rows go in before triggers exist, so replaying them fires nothing
+        for table in seed_tables {
+            if !objects.iter().any(|(object_type, name, _)| object_type == "table" && name == table) {
+                return Err(DatabaseError::InvalidData {
+                    message: format!("Seed table {} does not exist", table),
+                });
+            }
+            script.push_str(&self.seed_inserts(table).await?);
+        }
+
+        for kind in ["index", "view", "trigger"] {
+            script.extend(definitions(kind));
+        }
+        Ok(script)
+    }
+
+    // One `INSERT` per row of `table`, with values rendered by SQLite's `quote()`.
+    // The table is known to exist, so its names are quoted as stored.
+    async fn seed_inserts(&self, table: &str) -> Result<String> {
+        let quoted_table = quote_stored_identifier(table);
+        let columns = self
+            .column_names(table)
+            .await?
+            .iter()
+            .map(|column| quote_stored_identifier(column))
+            .collect::<Vec<_>>();
+        let values = columns
+            .iter()
+            .map(|column| format!("quote({})", column))
+            .collect::<Vec<_>>()
+            .join(" || ', ' || ");
+
+        let mut conn = self.connection()?.acquire().await?;
+        let rows: Vec<(String,)> = sqlx::query_as(&format!("SELECT {} FROM {}", values, quoted_table))
+            .fetch_all(&mut *conn)
+            .await?;
+
+        let prefix = format!("INSERT INTO {} ({}) VALUES", quoted_table, columns.join(", "));
+        Ok(rows
+            .into_iter()
+            .map(|(values,)| format!("{} ({});\n", prefix, values))
+            .collect())
+    }
+}
+
+fn parse_file_stem(stem: &str) -> Result<(i64, String)> {
+    stem.split_once('_')
+        .and_then(|(version, name)| Some((version.parse().ok()?, name.to_string())))
+        .ok_or_else(|| {
+            DatabaseError::Migration(format!("Migration file {:?} is not named <version>_<name>.sql", stem))
+        })
+}
+
+// FNV-1a is enough to notice edits and, unlike `DefaultHasher`, is stable
+// across Rust releases.
+fn checksum(contents: &str) -> String {
+    let hash = contents.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,399 @@
+use std::collections::BTreeMap;
+
+use crate::database::Database;
+use crate::error::{DatabaseError, Result};
+use crate::sql::quote_identifier;
+use crate::value::{to_arguments, SqlValue};
+
+/// A column definition for `Database::create_table`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnSpec {
+    pub name: String,
+    pub sql_type: String,
+    pub primary_key: bool,
+    pub not_null: bool,
+    /// A `CHECK` expression applied to this column, e.g. `port BETWEEN 1 AND 65535`.
+    pub check: Option<String>,
+}
+
+impl ColumnSpec {
+    pub fn new(name: &str, sql_type: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            sql_type: sql_type.to_string(),
+            primary_key: false,
+            not_null: false,
+            check: None,
+        }
+    }
+
+    pub fn primary_key(mut self) -> Self {
+        self.primary_key = true;
+        self
+    }
+
+    pub fn not_null(mut self) -> Self {
+        self.not_null = true;
+        self
+    }
+
+    pub fn check(mut self, expression: &str) -> Self {
+        self.check = Some(expression.to_string());
+        self
+    }
+}
+
+/// A table definition for `Database::create_table`. Columns marked
+/// `primary_key` form a single (possibly composite) primary key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableSpec {
+    pub name: String,
+    pub columns: Vec<ColumnSpec>,
+    /// Table-level `CHECK` expressions, for invariants spanning columns.
+    pub checks: Vec<String>,
+    /// Emit `WITHOUT ROWID`; see `TableSpec::without_rowid`.
+    pub without_rowid: bool,
+}
+
+impl TableSpec {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            columns: Vec::new(),
+            checks: Vec::new(),
+            without_rowid: false,
+        }
+    }
+
+    pub fn column(mut self, column: ColumnSpec) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    pub fn check(mut self, expression: &str) -> Self {
+        self.checks.push(expression.to_string());
+        self
+    }
+
+    /// Stores the table as a `WITHOUT ROWID` table clustered on its primary
+    /// key, which saves the separate rowid b-tree for tables keyed by UUIDs or
+    /// other text. The table must have a primary key, and inserts into it do
+    /// not set `last_insert_rowid`.
+    pub fn without_rowid(mut self) -> Self {
+        self.without_rowid = true;
+        self
+    }
+
+    pub(crate) fn create_sql(&self) -> Result<String> {
+        if self.columns.is_empty() {
+            return Err(DatabaseError::InvalidData {
+                message: format!("Table {:?} has no columns", self.name),
+            });
+        }
+        if self.without_rowid && !self.columns.iter().any(|column| column.primary_key) {
+            return Err(DatabaseError::InvalidData {
+                message: format!("WITHOUT ROWID table {:?} needs a primary key", self.name),
+            });
+        }
+
+        let mut definitions = Vec::new();
+        let mut primary_key = Vec::new();
+        for column in &self.columns {
+            let name = quote_identifier(&column.name)?;
+            let mut definition = format!("{} {}", name, column.sql_type);
+            if column.not_null {
+                definition.push_str(" NOT NULL");
+            }
+            if let Some(check) = &column.check {
+                definition.push_str(&format!(" CHECK ({})", check));
+            }
+            if column.primary_key {
+                primary_key.push(name);
+            }
+            definitions.push(definition);
+        }
+
+        if !primary_key.is_empty() {
+            definitions.push(format!("PRIMARY KEY ({})", primary_key.join(", ")));
+        }
+        for check in &self.checks {
+            definitions.push(format!("CHECK ({})", check));
+        }
+
+        Ok(format!(
+            "CREATE TABLE IF NOT EXISTS {} ({}){}",
+            quote_identifier(&self.name)?,
+            definitions.join(", "),
+            if self.without_rowid { " WITHOUT ROWID" } else { "" }
+        ))
+    }
+}
+
+/// A single difference reported by `Database::schema_diff`. "Missing" means
+/// present in the reference database but not in this one; "Extra" the reverse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaDiff {
+    MissingTable { table: String },
+    ExtraTable { table: String },
+    MissingColumn { table: String, column: String },
+    ExtraColumn { table: String, column: String },
+    ColumnMismatch { table: String, column: String, expected: String, actual: String },
+    MissingIndex { table: String, index: String },
+    ExtraIndex { table: String, index: String },
+    IndexMismatch { table: String, index: String, expected: String, actual: String },
+}
+
+/// A trigger attached to a table, as stored in `sqlite_master`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggerInfo {
+    pub name: String,
+    pub sql: String,
+}
+
+/// A column as reported by `PRAGMA table_info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// The declared type, e.g. `INTEGER` or `VARCHAR(20)`; empty if none was declared.
+    pub data_type: String,
+    /// `false` for `NOT NULL` columns.
+    pub nullable: bool,
+    /// Whether the column is part of the primary key.
+    pub primary_key: bool,
+}
+
+#[derive(Debug, Default)]
+struct TableSchema {
+    columns: BTreeMap<String, String>,
+    indexes: BTreeMap<String, String>,
+}
+
+impl Database {
+    /// Lists the user tables in name order, excluding SQLite's internal
+    /// `sqlite_*` tables and this crate's `_meta`, `_locks` and `_migrations`.
+    pub async fn list_tables(&self) -> Result<Vec<String>> {
+        let mut conn = self.connection()?.acquire().await?;
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT name FROM sqlite_master
+             WHERE type = 'table' AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\'
+               AND name NOT IN ('_meta', '_locks', '_migrations')
+             ORDER BY name",
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(rows.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// Whether a table named `name` exists. Views and indexes do not count.
+    pub async fn table_exists(&self, name: &str) -> Result<bool> {
+        let mut conn = self.connection()?.acquire().await?;
+        let (exists,): (bool,) =
+            sqlx::query_as("SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?)")
+                .bind(name)
+                .fetch_one(&mut *conn)
+                .await?;
+        Ok(exists)
+    }
+
+    /// Describes the columns of `table` in declaration order. The name is
+    /// checked against the identifier policy and bound rather than spliced
+    /// into the pragma; a table that does not exist fails with `InvalidData`.
+    pub async fn table_columns(&self, table: &str) -> Result<Vec<ColumnInfo>> {
+        quote_identifier(table)?;
+        let mut conn = self.connection()?.acquire().await?;
+        let rows: Vec<(String, String, bool, i64)> =
+            sqlx::query_as("SELECT name, type, \"notnull\", pk FROM pragma_table_info(?) ORDER BY cid")
+                .bind(table)
+                .fetch_all(&mut *conn)
+                .await?;
+
+        if rows.is_empty() {
+            return Err(DatabaseError::InvalidData {
+                message: format!("Table {} does not exist", table),
+            });
+        }
+        Ok(rows
+            .into_iter()
+            .map(|(name, data_type, not_null, primary_key)| ColumnInfo {
+                name,
+                data_type,
+                nullable: !not_null,
+                primary_key: primary_key > 0,
+            })
+            .collect())
+    }
+
+    pub(crate) async fn column_names(&self, table: &str) -> Result<Vec<String>> {
+        let mut conn = self.connection()?.acquire().await?;
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT name FROM pragma_table_info(?) ORDER BY cid")
+            .bind(table)
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok(rows.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// Lists the triggers defined on `table`, in name order.
+    pub async fn triggers(&self, table: &str) -> Result<Vec<TriggerInfo>> {
+        let mut conn = self.connection()?.acquire().await?;
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT name, sql FROM sqlite_master WHERE type = 'trigger' AND tbl_name = ? ORDER BY name",
+        )
+        .bind(table)
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(rows.into_iter().map(|(name, sql)| TriggerInfo { name, sql }).collect())
+    }
+
+    /// Creates the table described by `spec` if it does not already exist.
+    pub async fn create_table(&self, spec: &TableSpec) -> Result<()> {
+        let query = spec.create_sql()?;
+        let mut conn = self.connection()?.acquire().await?;
+        sqlx::query(&query).execute(&mut *conn).await?;
+        Ok(())
+    }
+
+    /// Runs `EXPLAIN QUERY PLAN` for `query` and reports whether its main table
+    /// is read through an index rather than a full scan. Meant for tests that
+    /// guard against missing-index regressions.
+    pub async fn uses_index(&self, query: &str, params: &[SqlValue]) -> Result<bool> {
+        let mut conn = self.connection()?.acquire().await?;
+        let arguments = to_arguments(params, self.encoding());
+        let plan: Vec<(i64, i64, i64, String)> =
+            sqlx::query_as_with(&format!("EXPLAIN QUERY PLAN {}", query), arguments)
+                .fetch_all(&mut *conn)
+                .await?;
+
+        // The first SCAN/SEARCH step is the outermost loop, i.e. the main table
+        let main_step = plan
+            .iter()
+            .map(|(_, _, _, detail)| detail)
+            .find(|detail| detail.starts_with("SCAN ") || detail.starts_with("SEARCH "));
+
+        Ok(main_step.is_none_or(|detail| detail.contains(" USING ")))
+    }
+
+    /// Compares the tables, columns and indexes of this database against
+    /// `reference` and returns every difference, or an empty list when the
+    /// schemas match.
+    pub async fn schema_diff(&self, reference: &Database) -> Result<Vec<SchemaDiff>> {
+        let actual = self.schema_snapshot().await?;
+        let expected = reference.schema_snapshot().await?;
+        let mut diffs = Vec::new();
+
+        for (table, expected_table) in &expected {
+            let Some(actual_table) = actual.get(table) else {
+                diffs.push(SchemaDiff::MissingTable { table: table.clone() });
+                continue;
+            };
+
+            for (column, expected_def) in &expected_table.columns {
+                match actual_table.columns.get(column) {
+                    None => diffs.push(SchemaDiff::MissingColumn {
+                        table: table.clone(),
+                        column: column.clone(),
+                    }),
+                    Some(actual_def) if actual_def != expected_def => diffs.push(SchemaDiff::ColumnMismatch {
+                        table: table.clone(),
+                        column: column.clone(),
+                        expected: expected_def.clone(),
+                        actual: actual_def.clone(),
+                    }),
+                    Some(_) => {}
+                }
+            }
+            for column in actual_table.columns.keys() {
+                if !expected_table.columns.contains_key(column) {
+                    diffs.push(SchemaDiff::ExtraColumn {
+                        table: table.clone(),
+                        column: column.clone(),
+                    });
+                }
+            }
+
+            for (index, expected_def) in &expected_table.indexes {
+                match actual_table.indexes.get(index) {
+                    None => diffs.push(SchemaDiff::MissingIndex {
+                        table: table.clone(),
+                        index: index.clone(),
+                    }),
+                    Some(actual_def) if actual_def != expected_def => diffs.push(SchemaDiff::IndexMismatch {
+                        table: table.clone(),
+                        index: index.clone(),
+                        expected: expected_def.clone(),
+                        actual: actual_def.clone(),
+                    }),
+                    Some(_) => {}
+                }
+            }
+            for index in actual_table.indexes.keys() {
+                if !expected_table.indexes.contains_key(index) {
+                    diffs.push(SchemaDiff::ExtraIndex {
+                        table: table.clone(),
+                        index: index.clone(),
+                    });
+                }
+            }
+        }
+
+        for table in actual.keys() {
+            if !expected.contains_key(table) {
+                diffs.push(SchemaDiff::ExtraTable { table: table.clone() });
+            }
+        }
+
+        Ok(diffs)
+    }
+
+    // Describes each column and index as a comparable string, keyed by name.
+    async fn schema_snapshot(&self) -> Result<BTreeMap<String, TableSchema>> {
+        let tables = self.list_tables().await?;
+        let mut conn = self.connection()?.acquire().await?;
+        let mut snapshot = BTreeMap::new();
+
+        for table in tables {
+            let mut schema = TableSchema::default();
+
+            let columns: Vec<(String, String, bool, Option<String>, i64)> = sqlx::query_as(
+                "SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?) ORDER BY cid",
+            )
+            .bind(&table)
+            .fetch_all(&mut *conn)
+            .await?;
+            for (name, column_type, not_null, default, pk) in columns {
+                let mut definition = column_type.to_uppercase();
+                if not_null {
+                    definition.push_str(" NOT NULL");
+                }
+                if pk > 0 {
+                    definition.push_str(&format!(" PRIMARY KEY({})", pk));
+                }
+                if let Some(default) = default {
+                    definition.push_str(&format!(" DEFAULT {}", default));
+                }
+                schema.columns.insert(name, definition);
+            }
+
+            let indexes: Vec<(String, bool)> = sqlx::query_as("SELECT name, \"unique\" FROM pragma_index_list(?)")
+                .bind(&table)
+                .fetch_all(&mut *conn)
+                .await?;
+            for (name, unique) in indexes {
+                let columns: Vec<(Option<String>,)> =
+                    sqlx::query_as("SELECT name FROM pragma_index_info(?) ORDER BY seqno")
+                        .bind(&name)
+                        .fetch_all(&mut *conn)
+                        .await?;
+                let columns: Vec<String> = columns
+                    .into_iter()
+                    .map(|(column,)| column.unwrap_or_else(|| "<expression>".to_string()))
+                    .collect();
+                let definition = format!("{}({})", if unique { "UNIQUE " } else { "" }, columns.join(", "));
+                schema.indexes.insert(name, definition);
+            }
+
+            snapshot.insert(table, schema);
+        }
+
+        Ok(snapshot)
+    }
+}
@@ -0,0 +1,113 @@
+use crate::database::Database;
+use crate::error::Result;
+use crate::sql::{quote_identifier, strip_order_and_limit};
+use crate::types::{Page, QueryOptions};
+use crate::value::SqlValue;
+
+/// Builds a `SELECT` over one table with the sorting and paging of a
+/// `QueryOptions`. Table, column and `order_by` names are validated with
+/// `quote_identifier`; `LIMIT` and `OFFSET` are bound as parameters.
+#[derive(Debug, Clone)]
+pub struct SelectBuilder {
+    source: String,
+    columns: Vec<String>,
+    options: QueryOptions,
+}
+
+impl SelectBuilder {
+    /// Selects `columns` from `table`, or every column if `columns` is empty.
+    pub fn new(table: &str, columns: &[&str]) -> Self {
+        Self {
+            source: table.to_string(),
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+            options: QueryOptions::default(),
+        }
+    }
+
+    pub fn options(mut self, options: &QueryOptions) -> Self {
+        self.options = options.clone();
+        self
+    }
+
+    /// Returns the SQL and the parameters to bind to it, in order.
+    pub fn build(&self) -> Result<(String, Vec<SqlValue>)> {
+        self.build_from(&quote_identifier(&self.source)?)
+    }
+
+    // `from` is spliced in as is, so it must already be quoted or be a
+    // parenthesized subquery.
+    pub(crate) fn build_from(&self, from: &str) -> Result<(String, Vec<SqlValue>)> {
+        let columns = if self.columns.is_empty() {
+            "*".to_string()
+        } else {
+            self.columns
+                .iter()
+                .map(|column| quote_identifier(column))
+                .collect::<Result<Vec<_>>>()?
+                .join(", ")
+        };
+        let mut sql = format!("SELECT {} FROM {}", columns, from);
+        let mut params = Vec::new();
+
+        if let Some(order_by) = &self.options.order_by {
+            sql.push_str(&format!(
+                " ORDER BY {} {}",
+                quote_identifier(order_by)?,
+                self.options.order_direction.as_sql()
+            ));
+        }
+        // SQLite only accepts OFFSET after a LIMIT; -1 means no limit
+        if self.options.limit.is_some() || self.options.offset.is_some() {
+            sql.push_str(" LIMIT ?");
+            params.push(SqlValue::Integer(self.options.limit.map_or(-1, clamp_to_i64)));
+        }
+        if let Some(offset) = self.options.offset {
+            sql.push_str(" OFFSET ?");
+            params.push(SqlValue::Integer(clamp_to_i64(offset)));
+        }
+
+        Ok((sql, params))
+    }
+}
+
+fn clamp_to_i64(value: u64) -> i64 {
+    value.min(i64::MAX as u64) as i64
+}
+
+impl Database {
+    /// Reads `columns` (all of them if empty) from `table`, sorted and paged
+    /// according to `options`; see `SelectBuilder`.
+    pub async fn select<T>(&self, table: &str, columns: &[&str], options: &QueryOptions) -> Result<Vec<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    {
+        let (sql, params) = SelectBuilder::new(table, columns).options(options).build()?;
+        self.fetch_all_with(&sql, &params).await
+    }
+
+    /// Runs `base_query` with the sorting and paging of `options`, plus a
+    /// `COUNT(*)` over the same query with its own `ORDER BY` and `LIMIT`
+    /// removed for `Page::total`. The two queries are not run in one
+    /// transaction, so concurrent writes can make them disagree.
+    pub async fn paginate<T>(&self, base_query: &str, options: &QueryOptions) -> Result<Page<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    {
+        let base_query = base_query.trim_end_matches(|c: char| c == ';' || c.is_whitespace());
+        // Newlines keep a trailing `--` comment from swallowing the parenthesis
+        let (sql, params) = SelectBuilder::new("", &[])
+            .options(options)
+            .build_from(&format!("({}\n)", base_query))?;
+        let items = self.fetch_all_with(&sql, &params).await?;
+
+        let count_query = format!("SELECT COUNT(*) FROM ({}\n)", strip_order_and_limit(base_query));
+        let (total,): (i64,) = self.fetch_one(&count_query).await?;
+
+        Ok(Page {
+            items,
+            total: total as u64,
+            limit: options.limit,
+            offset: options.offset.unwrap_or(0),
+        })
+    }
+}
@@ -0,0 +1,339 @@
+use std::ffi::CString;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::{DatabaseError, Result};
+
+/// How `quote_identifier`, and therefore every helper that takes a table or
+/// column name, treats names that are not plain SQL identifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentifierPolicy {
+    /// Only `[A-Za-z_][A-Za-z0-9_]*` names are accepted; anything else is
+    /// rejected with `InvalidData`. This is the default.
+    Strict,
+    /// Any non-empty name is accepted and quoted, with embedded double quotes
+    /// escaped.
+    Permissive,
+}
+
+static PERMISSIVE_IDENTIFIERS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_identifier_policy(policy: IdentifierPolicy) {
+    PERMISSIVE_IDENTIFIERS.store(policy == IdentifierPolicy::Permissive, Ordering::SeqCst);
+}
+
+pub fn identifier_policy() -> IdentifierPolicy {
+    if PERMISSIVE_IDENTIFIERS.load(Ordering::SeqCst) {
+        IdentifierPolicy::Permissive
+    } else {
+        IdentifierPolicy::Strict
+    }
+}
+
+fn is_valid_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Validates a table or column name according to the current
+/// `IdentifierPolicy` and returns it double-quoted for use in SQL.
+pub fn quote_identifier(name: &str) -> Result<String> {
+    let acceptable = match identifier_policy() {
+        IdentifierPolicy::Strict => is_valid_identifier(name),
+        IdentifierPolicy::Permissive => !name.is_empty() && !name.contains('\0'),
+    };
+
+    if !acceptable {
+        return Err(DatabaseError::InvalidData {
+            message: format!("Invalid SQL identifier: {:?}", name),
+        });
+    }
+
+    Ok(quote_stored_identifier(name))
+}
+
+// Double-quotes a name read back from the schema. Such names already exist,
+// so they are quoted as-is whatever the `IdentifierPolicy`.
+pub(crate) fn quote_stored_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// SQLite's historical default for `SQLITE_MAX_VARIABLE_NUMBER`. Newer builds
+/// allow 32766, but staying under the old default works everywhere.
+pub(crate) const DEFAULT_MAX_VARIABLES: usize = 999;
+
+/// The compile-time `SQLITE_MAX_VARIABLE_NUMBER` of the bundled SQLite; the
+/// runtime limit can be lowered below this but never raised above it.
+pub(crate) const MAX_VARIABLES_CEILING: usize = 32766;
+
+// Splits `items` into slices whose total bind count stays within
+// `max_variables`, so multi-bind helpers can issue one statement per slice.
+pub(crate) fn chunk_params<T>(items: &[T], per_row_binds: usize, max_variables: usize) -> std::slice::Chunks<'_, T> {
+    let rows_per_chunk = (max_variables / per_row_binds.max(1)).max(1);
+    items.chunks(rows_per_chunk)
+}
+
+/// Produces a canonical form of a statement for grouping query statistics:
+/// comments are dropped, whitespace is collapsed and string, blob and numeric
+/// literals are replaced with `?`.
+pub fn normalize_sql(sql: &str) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut normalized = String::with_capacity(sql.len());
+    let mut pending_space = false;
+    let mut i = 0;
+
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+
+    while i < chars.len() {
+        let c = chars[i];
+        let prev_is_word = normalized.chars().last().is_some_and(is_word_char) && !pending_space;
+
+        if c.is_whitespace() {
+            pending_space = true;
+            i += 1;
+            continue;
+        } else if c == '-' && chars.get(i + 1) == Some(&'-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            pending_space = true;
+            continue;
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+            pending_space = true;
+            continue;
+        }
+
+        if pending_space && !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        pending_space = false;
+
+        if c == '\'' || ((c == 'x' || c == 'X') && !prev_is_word && chars.get(i + 1) == Some(&'\'')) {
+            // String or blob literal, honouring '' escapes
+            i += if c == '\'' { 1 } else { 2 };
+            while i < chars.len() {
+                if chars[i] == '\'' {
+                    if chars.get(i + 1) == Some(&'\'') {
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    break;
+                }
+                i += 1;
+            }
+            normalized.push('?');
+        } else if c == '"' || c == '`' || c == '[' {
+            // Quoted identifiers are kept verbatim
+            let close = if c == '[' { ']' } else { c };
+            normalized.push(c);
+            i += 1;
+            while i < chars.len() {
+                normalized.push(chars[i]);
+                i += 1;
+                if chars[i - 1] == close {
+                    break;
+                }
+            }
+        } else if matches!(c, '?' | ':' | '@' | '$') {
+            // Bind parameters such as ?1 or :name are not literals
+            normalized.push(c);
+            i += 1;
+            while i < chars.len() && is_word_char(chars[i]) {
+                normalized.push(chars[i]);
+                i += 1;
+            }
+        } else if (c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())))
+            && !prev_is_word
+        {
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric()
+                    || chars[i] == '.'
+                    || ((chars[i] == '+' || chars[i] == '-') && matches!(chars[i - 1], 'e' | 'E')))
+            {
+                i += 1;
+            }
+            normalized.push('?');
+        } else {
+            normalized.push(c);
+            i += 1;
+        }
+    }
+
+    normalized.trim_end_matches(|c: char| c == ';' || c.is_whitespace()).to_string()
+}
+
+// Splits a script into its statements. Boundaries are found with SQLite's own
+// `sqlite3_complete`, so semicolons inside literals, comments and trigger
+// bodies do not split. Empty and comment-only statements are dropped.
+pub(crate) fn split_statements(script: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for (index, _) in script.match_indices(';') {
+        if is_complete_statement(&script[start..=index]) {
+            segments.push(&script[start..=index]);
+            start = index + 1;
+        }
+    }
+    segments.push(&script[start..]);
+
+    segments
+        .into_iter()
+        .filter(|statement| !normalize_sql(statement).is_empty())
+        .map(str::trim)
+        .collect()
+}
+
+// Cuts a query before its top-level `ORDER BY` or `LIMIT`, so it can be
+// wrapped in a `COUNT(*)`. Clauses inside parentheses (subqueries, window
+// definitions), literals, quoted identifiers and comments are left alone.
+pub(crate) fn strip_order_and_limit(query: &str) -> &str {
+    let bytes = query.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' | b'`' | b'[' => {
+                let close = if bytes[i] == b'[' { b']' } else { bytes[i] };
+                i += 1;
+                while i < bytes.len() && bytes[i] != close {
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'(' => depth += 1,
+            b')' => depth = depth.saturating_sub(1),
+            c if depth == 0 && c.is_ascii_alphabetic() && (i == 0 || !is_word_byte(bytes[i - 1])) => {
+                let end = bytes[i..].iter().position(|&b| !is_word_byte(b)).map_or(bytes.len(), |n| i + n);
+                let word = &query[i..end];
+                let next_word = query[end..].trim_start();
+                let is_order_by = word.eq_ignore_ascii_case("ORDER")
+                    && next_word.get(..2).is_some_and(|by| by.eq_ignore_ascii_case("BY"))
+                    && !next_word.as_bytes().get(2).is_some_and(|&b| is_word_byte(b));
+                if is_order_by || word.eq_ignore_ascii_case("LIMIT") {
+                    return query[..i].trim_end();
+                }
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    query.trim_end_matches(|c: char| c == ';' || c.is_whitespace())
+}
+
+// Multi-byte UTF-8 sequences count as word characters, like in SQLite
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+fn is_complete_statement(sql: &str) -> bool {
+    // SAFETY: the pointer is a valid NUL-terminated string for the duration of the call
+    CString::new(sql).is_ok_and(|sql| unsafe { libsqlite3_sys::sqlite3_complete(sql.as_ptr()) } != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("users").unwrap(), "\"users\"");
+        assert_eq!(quote_identifier("_meta_2").unwrap(), "\"_meta_2\"");
+
+        assert!(quote_identifier("").is_err());
+        assert!(quote_identifier("1users").is_err());
+        assert!(quote_identifier("users; DROP TABLE x").is_err());
+        assert!(quote_identifier("us\"ers").is_err());
+    }
+
+    #[test]
+    fn test_chunk_params_respects_variable_limit() {
+        let ids: Vec<u32> = (0..2500).collect();
+        let chunks: Vec<&[u32]> = chunk_params(&ids, 1, DEFAULT_MAX_VARIABLES).collect();
+        assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<_>>(), vec![999, 999, 502]);
+
+        let rows: Vec<u32> = (0..700).collect();
+        assert!(chunk_params(&rows, 3, DEFAULT_MAX_VARIABLES).all(|chunk| chunk.len() * 3 <= DEFAULT_MAX_VARIABLES));
+        assert_eq!(chunk_params(&rows, 3, DEFAULT_MAX_VARIABLES).count(), 3);
+        assert_eq!(chunk_params(&rows, 5000, DEFAULT_MAX_VARIABLES).count(), 700);
+    }
+
+    #[test]
+    fn test_split_statements() {
+        let script = "CREATE TABLE t (a TEXT); -- note; not a statement
+            INSERT INTO t VALUES ('x;y');;
+            CREATE TRIGGER tr AFTER INSERT ON t BEGIN DELETE FROM t WHERE a = ''; END;
+            SELECT * FROM t";
+        assert_eq!(
+            split_statements(script),
+            vec![
+                "CREATE TABLE t (a TEXT);",
+                "-- note; not a statement\n            INSERT INTO t VALUES ('x;y');",
+                "CREATE TRIGGER tr AFTER INSERT ON t BEGIN DELETE FROM t WHERE a = ''; END;",
+                "SELECT * FROM t",
+            ]
+        );
+        assert!(split_statements("  ; -- nothing\n").is_empty());
+    }
+
+    #[test]
+    fn test_strip_order_and_limit() {
+        assert_eq!(strip_order_and_limit("SELECT * FROM t ORDER BY id LIMIT 5"), "SELECT * FROM t");
+        assert_eq!(strip_order_and_limit("SELECT * FROM t limit 5 offset 2;"), "SELECT * FROM t");
+        assert_eq!(strip_order_and_limit("SELECT * FROM t WHERE a = 1;\n"), "SELECT * FROM t WHERE a = 1");
+        assert_eq!(
+            strip_order_and_limit("SELECT * FROM (SELECT id FROM t ORDER BY id LIMIT 1) WHERE note = 'order by'"),
+            "SELECT * FROM (SELECT id FROM t ORDER BY id LIMIT 1) WHERE note = 'order by'"
+        );
+        assert_eq!(
+            strip_order_and_limit("SELECT \"limit\", orderly, border FROM t -- LIMIT\nORDER\n BY 1"),
+            "SELECT \"limit\", orderly, border FROM t -- LIMIT"
+        );
+    }
+
+    #[test]
+    fn test_normalize_sql_groups_equivalent_queries() {
+        let first = normalize_sql("SELECT id, name FROM users WHERE name = 'alice' AND age > 30");
+        let second = normalize_sql(
+            "SELECT  id,\n       name\nFROM users\n  WHERE name = 'bob''s'   AND age > 4.5e2;",
+        );
+
+        assert_eq!(first, "SELECT id, name FROM users WHERE name = ? AND age > ?");
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_normalize_sql_preserves_identifiers() {
+        assert_eq!(
+            normalize_sql("SELECT t1.col2 FROM \"table 3\" t1 -- trailing comment\nLIMIT 10"),
+            "SELECT t1.col2 FROM \"table 3\" t1 LIMIT ?"
+        );
+        assert_eq!(
+            normalize_sql("INSERT INTO blobs VALUES (X'0A0B', -1, 0x1F) /* note */"),
+            "INSERT INTO blobs VALUES (?, -?, ?)"
+        );
+        assert_eq!(normalize_sql("SELECT ?1, :name"), "SELECT ?1, :name");
+    }
+}
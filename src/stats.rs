@@ -0,0 +1,82 @@
+use sqlx::SqlitePool;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::time::Duration;
+
+// Every pool created by the crate, held weakly so dropping a `Database` still
+// releases its connections.
+static OPEN_POOLS: Mutex<Vec<Weak<SqlitePool>>> = Mutex::new(Vec::new());
+
+/// Pool counters. Acquire and execute times are kept apart so slowness can be
+/// attributed to pool contention or to the SQL itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Connections currently open, idle or in use.
+    pub size: u32,
+    /// Open connections waiting in the pool.
+    pub idle: usize,
+    pub max_connections: u32,
+    pub acquire_failures: u64,
+    /// Successful checkouts through `DatabaseConnection::acquire`.
+    pub acquires: u64,
+    /// Total time spent waiting for those checkouts.
+    pub total_acquire_time: Duration,
+    /// Statements timed by the `execute_query`, `query` and `fetch_*` methods.
+    pub executions: u64,
+    /// Total time spent running those statements.
+    pub total_execute_time: Duration,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct PoolMetrics {
+    acquire_failures: AtomicU64,
+    acquires: AtomicU64,
+    acquire_nanos: AtomicU64,
+    executions: AtomicU64,
+    execute_nanos: AtomicU64,
+}
+
+impl PoolMetrics {
+    pub(crate) fn record_acquire_failure(&self) {
+        self.acquire_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_acquire(&self, elapsed: Duration) {
+        self.acquires.fetch_add(1, Ordering::Relaxed);
+        self.acquire_nanos.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_execute(&self, elapsed: Duration) {
+        self.executions.fetch_add(1, Ordering::Relaxed);
+        self.execute_nanos.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    // The counters only; the pool gauges are filled in by `DatabaseConnection::stats`.
+    pub(crate) fn snapshot(&self) -> PoolStats {
+        PoolStats {
+            size: 0,
+            idle: 0,
+            max_connections: 0,
+            acquire_failures: self.acquire_failures.load(Ordering::Relaxed),
+            acquires: self.acquires.load(Ordering::Relaxed),
+            total_acquire_time: Duration::from_nanos(self.acquire_nanos.load(Ordering::Relaxed)),
+            executions: self.executions.load(Ordering::Relaxed),
+            total_execute_time: Duration::from_nanos(self.execute_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+pub(crate) fn register_pool(pool: &Arc<SqlitePool>) {
+    let mut pools = OPEN_POOLS.lock().unwrap_or_else(PoisonError::into_inner);
+    pools.retain(|pool| pool.strong_count() > 0);
+    pools.push(Arc::downgrade(pool));
+}
+
+pub(crate) fn global_open_connections() -> usize {
+    let pools = OPEN_POOLS.lock().unwrap_or_else(PoisonError::into_inner);
+    pools
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|pool| pool.size() as usize)
+        .sum()
+}
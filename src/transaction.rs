@@ -0,0 +1,232 @@
+use sqlx::{Sqlite, Transaction};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::database::{Database, DatabaseConnection};
+use crate::error::{DatabaseError, Result};
+use crate::sql::quote_identifier;
+
+/// The future returned by closures passed to `Database::transaction`; build
+/// one with `Box::pin(async move { ... })`.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+struct TransactionState {
+    tx: Option<Transaction<'static, Sqlite>>,
+    deadline: Option<Instant>,
+    // Quoted names of savepoints dropped without commit or rollback; they are
+    // rolled back before the transaction is next used.
+    pending_rollbacks: Vec<String>,
+}
+
+impl TransactionState {
+    // Returns the open transaction, rolling it back first if its deadline has
+    // passed and unwinding any abandoned savepoints.
+    async fn active(&mut self) -> Result<&mut Transaction<'static, Sqlite>> {
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            if let Some(tx) = self.tx.take() {
+                let _ = tx.rollback().await;
+            }
+        }
+
+        let tx = self.tx.as_mut().ok_or(DatabaseError::Timeout)?;
+        for name in self.pending_rollbacks.drain(..) {
+            sqlx::query(&format!("ROLLBACK TO {}", name)).execute(&mut **tx).await?;
+            sqlx::query(&format!("RELEASE {}", name)).execute(&mut **tx).await?;
+        }
+        Ok(tx)
+    }
+}
+
+pub struct DatabaseTransaction {
+    state: Arc<Mutex<TransactionState>>,
+}
+
+impl DatabaseTransaction {
+    pub(crate) async fn begin(connection: &DatabaseConnection, timeout: Option<Duration>) -> Result<Self> {
+        let tx = connection.begin().await?;
+        let state = Arc::new(Mutex::new(TransactionState {
+            tx: Some(tx),
+            deadline: timeout.map(|timeout| Instant::now() + timeout),
+            pending_rollbacks: Vec::new(),
+        }));
+
+        if let Some(timeout) = timeout {
+            spawn_watchdog(Arc::downgrade(&state), timeout);
+        }
+
+        Ok(Self { state })
+    }
+
+    pub async fn execute_query(&mut self, query: &str) -> Result<sqlx::sqlite::SqliteQueryResult> {
+        let mut state = self.state.lock().await;
+        let tx = state.active().await?;
+        let result = sqlx::query(query).execute(&mut **tx).await?;
+        Ok(result)
+    }
+
+    pub async fn fetch_one<T>(&mut self, query: &str) -> Result<T>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    {
+        let mut state = self.state.lock().await;
+        let tx = state.active().await?;
+        let result = sqlx::query_as::<_, T>(query).fetch_one(&mut **tx).await?;
+        Ok(result)
+    }
+
+    pub async fn fetch_all<T>(&mut self, query: &str) -> Result<Vec<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    {
+        let mut state = self.state.lock().await;
+        let tx = state.active().await?;
+        let results = sqlx::query_as::<_, T>(query).fetch_all(&mut **tx).await?;
+        Ok(results)
+    }
+
+    /// Opens a savepoint inside this transaction. The returned guard releases
+    /// it on `commit`; `rollback`, or dropping the guard, undoes everything
+    /// done since the savepoint while keeping earlier work. `name` must be a
+    /// valid SQL identifier.
+    pub async fn savepoint(&mut self, name: &str) -> Result<Savepoint<'_>> {
+        let name = quote_identifier(name)?;
+        self.execute_query(&format!("SAVEPOINT {}", name)).await?;
+        Ok(Savepoint {
+            tx: self,
+            name,
+            finished: false,
+        })
+    }
+
+    pub async fn commit(self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        state.active().await?;
+        if let Some(tx) = state.tx.take() {
+            tx.commit().await?;
+        }
+        Ok(())
+    }
+
+    pub async fn rollback(self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        state.active().await?;
+        if let Some(tx) = state.tx.take() {
+            tx.rollback().await?;
+        }
+        Ok(())
+    }
+}
+
+/// A savepoint opened with `DatabaseTransaction::savepoint`.
+pub struct Savepoint<'t> {
+    tx: &'t mut DatabaseTransaction,
+    name: String,
+    finished: bool,
+}
+
+impl Savepoint<'_> {
+    pub async fn execute_query(&mut self, query: &str) -> Result<sqlx::sqlite::SqliteQueryResult> {
+        self.tx.execute_query(query).await
+    }
+
+    pub async fn fetch_one<T>(&mut self, query: &str) -> Result<T>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    {
+        self.tx.fetch_one(query).await
+    }
+
+    pub async fn fetch_all<T>(&mut self, query: &str) -> Result<Vec<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    {
+        self.tx.fetch_all(query).await
+    }
+
+    /// Opens a savepoint nested inside this one.
+    pub async fn savepoint(&mut self, name: &str) -> Result<Savepoint<'_>> {
+        self.tx.savepoint(name).await
+    }
+
+    pub async fn commit(mut self) -> Result<()> {
+        self.finished = true;
+        self.tx.execute_query(&format!("RELEASE {}", self.name)).await?;
+        Ok(())
+    }
+
+    pub async fn rollback(mut self) -> Result<()> {
+        self.finished = true;
+        self.tx.execute_query(&format!("ROLLBACK TO {}", self.name)).await?;
+        self.tx.execute_query(&format!("RELEASE {}", self.name)).await?;
+        Ok(())
+    }
+}
+
+impl Drop for Savepoint<'_> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        // Rolling back needs to await, so leave it for the transaction's next use.
+        // The lock is only contended by the timeout watchdog, which discards
+        // the whole transaction anyway.
+        if let Ok(mut state) = self.tx.state.try_lock() {
+            state.pending_rollbacks.push(std::mem::take(&mut self.name));
+        }
+    }
+}
+
+// Rolls the transaction back once its window elapses so a forgotten handle
+// doesn't keep holding the write lock. Only a weak reference is kept, so
+// dropping the handle still rolls back immediately.
+fn spawn_watchdog(state: Weak<Mutex<TransactionState>>, timeout: Duration) {
+    tokio::spawn(async move {
+        tokio::time::sleep(timeout).await;
+        if let Some(state) = state.upgrade() {
+            let mut state = state.lock().await;
+            if let Some(tx) = state.tx.take() {
+                let _ = tx.rollback().await;
+            }
+        }
+    });
+}
+
+impl Database {
+    /// Begins a transaction. It is rolled back if it is dropped without
+    /// calling `commit`.
+    pub async fn begin(&self) -> Result<DatabaseTransaction> {
+        DatabaseTransaction::begin(self.connection()?, None).await
+    }
+
+    /// Runs `f` inside a transaction, committing if it returns `Ok` and rolling
+    /// back if it returns `Err` or panics. The closure's error is returned
+    /// unchanged.
+    pub async fn transaction<F, T>(&self, f: F) -> Result<T>
+    where
+        F: for<'t> FnOnce(&'t mut DatabaseTransaction) -> BoxFuture<'t, Result<T>>,
+    {
+        // A panic unwinds past `tx`, and dropping it rolls back
+        let mut tx = self.begin().await?;
+        match f(&mut tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+                Ok(value)
+            }
+            Err(e) => {
+                let _ = tx.rollback().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Begins a transaction that is rolled back automatically if it is not
+    /// committed or rolled back within `timeout`. Any use of the transaction
+    /// after that point returns `DatabaseError::Timeout`.
+    pub async fn begin_with_timeout(&self, timeout: Duration) -> Result<DatabaseTransaction> {
+        DatabaseTransaction::begin(self.connection()?, Some(timeout)).await
+    }
+}
@@ -0,0 +1,227 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::database::PoolConfig;
+use crate::error::{DatabaseError, Result};
+use crate::value::SqlValue;
+
+/// A dynamically typed query result, as returned by `Database::run`: each
+/// row is a map from column name to its JSON value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueryResult {
+    pub rows: Vec<HashMap<String, serde_json::Value>>,
+    pub rows_affected: u64,
+    pub last_insert_id: Option<i64>,
+}
+
+impl QueryResult {
+    /// Converts each row into `T` through serde. Rows that don't fit `T`
+    /// (missing fields, mismatched types) are reported as `InvalidData`.
+    pub fn deserialize_rows<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let object = row.clone().into_iter().collect();
+                serde_json::from_value(serde_json::Value::Object(object)).map_err(|e| DatabaseError::InvalidData {
+                    message: format!("Row {} could not be deserialized: {}", index, e),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Sorting and paging applied by `Database::select` and `Database::paginate`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryOptions {
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    /// Column to sort by; must be a valid identifier.
+    pub order_by: Option<String>,
+    pub order_direction: OrderDirection,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrderDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl OrderDirection {
+    pub(crate) fn as_sql(self) -> &'static str {
+        match self {
+            OrderDirection::Asc => "ASC",
+            OrderDirection::Desc => "DESC",
+        }
+    }
+}
+
+/// One page of rows from `Database::paginate`, with the number of rows the
+/// query matches across all pages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub limit: Option<u64>,
+    pub offset: u64,
+}
+
+/// A query result stored column by column: one typed vector per column, all
+/// of length `num_rows`, with `None` for SQL `NULL`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnBatch {
+    pub num_rows: usize,
+    pub columns: Vec<Column>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub values: ColumnValues,
+}
+
+/// The values of one column. The variant is chosen from the storage classes
+/// actually present: integers mixed with reals become `Real`, and columns
+/// mixing other classes (or holding only `NULL`) fall back to `Any`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnValues {
+    Integer(Vec<Option<i64>>),
+    Real(Vec<Option<f64>>),
+    Text(Vec<Option<String>>),
+    Blob(Vec<Option<Vec<u8>>>),
+    Any(Vec<SqlValue>),
+}
+
+impl ColumnBatch {
+    pub fn column(&self, name: &str) -> Option<&ColumnValues> {
+        self.columns
+            .iter()
+            .find(|column| column.name == name)
+            .map(|column| &column.values)
+    }
+
+    // Builds the batch from decoded rows, given in column order.
+    pub(crate) fn from_rows(names: Vec<String>, rows: Vec<Vec<SqlValue>>) -> Self {
+        let num_rows = rows.len();
+        let mut per_column: Vec<Vec<SqlValue>> = names.iter().map(|_| Vec::with_capacity(num_rows)).collect();
+        for row in rows {
+            for (values, value) in per_column.iter_mut().zip(row) {
+                values.push(value);
+            }
+        }
+
+        let columns = names
+            .into_iter()
+            .zip(per_column)
+            .map(|(name, values)| Column {
+                name,
+                values: ColumnValues::from_values(values),
+            })
+            .collect();
+
+        Self { num_rows, columns }
+    }
+}
+
+impl ColumnValues {
+    pub fn len(&self) -> usize {
+        match self {
+            ColumnValues::Integer(values) => values.len(),
+            ColumnValues::Real(values) => values.len(),
+            ColumnValues::Text(values) => values.len(),
+            ColumnValues::Blob(values) => values.len(),
+            ColumnValues::Any(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn from_values(values: Vec<SqlValue>) -> Self {
+        let non_null = || values.iter().filter(|value| !value.is_null());
+        let all = |f: fn(&SqlValue) -> bool| non_null().next().is_some() && non_null().all(f);
+
+        if all(|value| matches!(value, SqlValue::Integer(_))) {
+            ColumnValues::Integer(
+                values
+                    .into_iter()
+                    .map(|value| match value {
+                        SqlValue::Integer(value) => Some(value),
+                        _ => None,
+                    })
+                    .collect(),
+            )
+        } else if all(|value| matches!(value, SqlValue::Integer(_) | SqlValue::Real(_))) {
+            ColumnValues::Real(
+                values
+                    .into_iter()
+                    .map(|value| match value {
+                        SqlValue::Integer(value) => Some(value as f64),
+                        SqlValue::Real(value) => Some(value),
+                        _ => None,
+                    })
+                    .collect(),
+            )
+        } else if all(|value| matches!(value, SqlValue::Text(_))) {
+            ColumnValues::Text(
+                values
+                    .into_iter()
+                    .map(|value| match value {
+                        SqlValue::Text(value) => Some(value),
+                        _ => None,
+                    })
+                    .collect(),
+            )
+        } else if all(|value| matches!(value, SqlValue::Blob(_))) {
+            ColumnValues::Blob(
+                values
+                    .into_iter()
+                    .map(|value| match value {
+                        SqlValue::Blob(value) => Some(value),
+                        _ => None,
+                    })
+                    .collect(),
+            )
+        } else {
+            ColumnValues::Any(values)
+        }
+    }
+}
+
+/// Connection settings in the shape of a typical service configuration
+/// file, consumed by `Database::connect_with_config`. For SQLite, `database`
+/// is the file path (or `:memory:`) and the network fields `host`, `port`,
+/// `username`, `password` and `ssl` are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseConfig {
+    pub database: String,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Maximum connections in the pool.
+    pub pool_size: u32,
+    /// How long to wait for a pooled connection before failing.
+    pub timeout: Duration,
+    pub ssl: bool,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        let pool = PoolConfig::default();
+        Self {
+            database: ":memory:".to_string(),
+            host: None,
+            port: None,
+            username: None,
+            password: None,
+            pool_size: pool.max_connections,
+            timeout: pool.acquire_timeout,
+            ssl: false,
+        }
+    }
+}
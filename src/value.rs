@@ -0,0 +1,209 @@
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::sqlite::{SqliteArguments, SqliteRow};
+use sqlx::{Arguments, Column, Row, TypeInfo, ValueRef};
+
+use crate::error::Result;
+
+/// Another name for `SqlValue` when it is used as a bound parameter.
+pub type Param = SqlValue;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+    Bool(bool),
+    DateTime(DateTime<Utc>),
+}
+
+/// How `SqlValue::DateTime` parameters are stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateTimeStorage {
+    /// RFC 3339 text with microseconds and a `Z` suffix, e.g.
+    /// `2024-05-01T12:00:00.000000Z`. The fixed width keeps text comparisons
+    /// in chronological order.
+    #[default]
+    Rfc3339,
+    /// Whole seconds since the Unix epoch, as an integer.
+    UnixSeconds,
+}
+
+/// How `SqlValue::Bool` parameters are stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BoolStorage {
+    /// `0` or `1`, matching sqlx's own `bool` encoding.
+    #[default]
+    Integer,
+    /// The text `'true'` or `'false'`.
+    Text,
+}
+
+// Per-database choices for how parameters are encoded when bound.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ValueEncoding {
+    pub(crate) datetime: DateTimeStorage,
+    pub(crate) bool: BoolStorage,
+}
+
+impl SqlValue {
+    pub fn is_null(&self) -> bool {
+        matches!(self, SqlValue::Null)
+    }
+
+    /// Reads a boolean stored in either `BoolStorage` format: `0`/`1`
+    /// integers or `'true'`/`'false'` text. Returns `None` for anything else.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            SqlValue::Bool(value) => Some(*value),
+            SqlValue::Integer(0) => Some(false),
+            SqlValue::Integer(1) => Some(true),
+            SqlValue::Text(value) if value.eq_ignore_ascii_case("true") => Some(true),
+            SqlValue::Text(value) if value.eq_ignore_ascii_case("false") => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Converts to JSON: blobs become base64 strings, timestamps RFC 3339
+    /// strings and non-finite reals `null`.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            SqlValue::Null => serde_json::Value::Null,
+            SqlValue::Integer(value) => (*value).into(),
+            SqlValue::Real(value) => serde_json::Number::from_f64(*value)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            SqlValue::Text(value) => value.clone().into(),
+            SqlValue::Blob(value) => base64::engine::general_purpose::STANDARD.encode(value).into(),
+            SqlValue::Bool(value) => (*value).into(),
+            SqlValue::DateTime(value) => value.to_rfc3339_opts(SecondsFormat::Micros, true).into(),
+        }
+    }
+}
+
+impl From<i64> for SqlValue {
+    fn from(value: i64) -> Self {
+        SqlValue::Integer(value)
+    }
+}
+
+impl From<i32> for SqlValue {
+    fn from(value: i32) -> Self {
+        SqlValue::Integer(value as i64)
+    }
+}
+
+impl From<f64> for SqlValue {
+    fn from(value: f64) -> Self {
+        SqlValue::Real(value)
+    }
+}
+
+impl From<bool> for SqlValue {
+    fn from(value: bool) -> Self {
+        SqlValue::Bool(value)
+    }
+}
+
+impl From<String> for SqlValue {
+    fn from(value: String) -> Self {
+        SqlValue::Text(value)
+    }
+}
+
+impl From<&str> for SqlValue {
+    fn from(value: &str) -> Self {
+        SqlValue::Text(value.to_string())
+    }
+}
+
+impl From<Vec<u8>> for SqlValue {
+    fn from(value: Vec<u8>) -> Self {
+        SqlValue::Blob(value)
+    }
+}
+
+impl From<DateTime<Utc>> for SqlValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        SqlValue::DateTime(value)
+    }
+}
+
+impl<T: Into<SqlValue>> From<Option<T>> for SqlValue {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(SqlValue::Null)
+    }
+}
+
+pub(crate) fn to_arguments(params: &[SqlValue], encoding: ValueEncoding) -> SqliteArguments<'static> {
+    let mut arguments = SqliteArguments::default();
+
+    for param in params {
+        match param {
+            SqlValue::Null => arguments.add(None::<i64>),
+            SqlValue::Integer(value) => arguments.add(*value),
+            SqlValue::Real(value) => arguments.add(*value),
+            SqlValue::Text(value) => arguments.add(value.clone()),
+            SqlValue::Blob(value) => arguments.add(value.clone()),
+            SqlValue::Bool(value) => match encoding.bool {
+                BoolStorage::Integer => arguments.add(*value as i64),
+                BoolStorage::Text => arguments.add(if *value { "true" } else { "false" }),
+            },
+            SqlValue::DateTime(value) => match encoding.datetime {
+                DateTimeStorage::Rfc3339 => arguments.add(value.to_rfc3339_opts(SecondsFormat::Micros, true)),
+                DateTimeStorage::UnixSeconds => arguments.add(value.timestamp()),
+            },
+        }
+    }
+
+    arguments
+}
+
+// Renders bound parameters for logs. Blobs are reduced to their length so
+// binary payloads never end up in log output.
+pub(crate) fn describe_params(params: &[SqlValue]) -> String {
+    let rendered: Vec<String> = params
+        .iter()
+        .map(|param| match param {
+            SqlValue::Blob(bytes) => format!("<blob {} bytes>", bytes.len()),
+            SqlValue::Text(text) => format!("{:?}", text),
+            other => other.to_json().to_string(),
+        })
+        .collect();
+    format!("[{}]", rendered.join(", "))
+}
+
+pub(crate) fn describe_text_params(params: &[String]) -> String {
+    describe_params(&params.iter().cloned().map(SqlValue::Text).collect::<Vec<_>>())
+}
+
+// Decodes a column by its runtime storage class rather than its declared type,
+// so rows can be read without a FromRow target.
+pub(crate) fn decode_column(row: &SqliteRow, index: usize) -> Result<SqlValue> {
+    let raw = row.try_get_raw(index)?;
+    if raw.is_null() {
+        return Ok(SqlValue::Null);
+    }
+
+    let value = match raw.type_info().name() {
+        "INTEGER" => SqlValue::Integer(row.try_get_unchecked(index)?),
+        "REAL" => SqlValue::Real(row.try_get_unchecked(index)?),
+        "BLOB" => SqlValue::Blob(row.try_get_unchecked(index)?),
+        _ => SqlValue::Text(row.try_get_unchecked(index)?),
+    };
+    Ok(value)
+}
+
+pub(crate) fn decode_row(row: &SqliteRow) -> Result<Vec<SqlValue>> {
+    (0..row.len()).map(|index| decode_column(row, index)).collect()
+}
+
+// Maps a row to a JSON object keyed by column name.
+pub(crate) fn row_to_json(row: &SqliteRow) -> Result<serde_json::Map<String, serde_json::Value>> {
+    row.columns()
+        .iter()
+        .map(|column| Ok((column.name().to_string(), decode_column(row, column.ordinal())?.to_json())))
+        .collect()
+}
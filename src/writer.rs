@@ -0,0 +1,139 @@
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use crate::database::{Database, DatabaseConnection};
+use crate::error::{DatabaseError, Result};
+use crate::value::{to_arguments, SqlValue, ValueEncoding};
+
+// Upper bound on the statements committed in one transaction, so a busy
+// queue still commits regularly.
+const MAX_BATCH: usize = 512;
+
+enum Command {
+    Write(String, Vec<SqlValue>),
+    Flush(oneshot::Sender<Result<()>>),
+}
+
+/// A handle to a background task that commits enqueued statements in
+/// batches, for writes such as request and system logs where the caller
+/// should not wait for the database.
+///
+/// Durability: a statement is only on disk once the batch holding it has
+/// committed. Anything still queued is lost if the process crashes; call
+/// `flush` to wait for everything enqueued so far. Dropping the handle lets
+/// the task drain the queue in the background, and `shutdown` waits for it.
+pub struct WriterQueue {
+    sender: mpsc::Sender<Command>,
+    task: JoinHandle<()>,
+    capacity: usize,
+}
+
+impl WriterQueue {
+    /// Queues `statement` without waiting. Fails with `Query` if the queue
+    /// already holds `capacity` statements or has shut down.
+    pub fn enqueue(&self, statement: impl Into<String>, params: Vec<SqlValue>) -> Result<()> {
+        self.sender
+            .try_send(Command::Write(statement.into(), params))
+            .map_err(|e| match e {
+                mpsc::error::TrySendError::Full(_) => {
+                    DatabaseError::Query(format!("Writer queue is full ({} statements)", self.capacity))
+                }
+                mpsc::error::TrySendError::Closed(_) => DatabaseError::Query("Writer queue has shut down".to_string()),
+            })
+    }
+
+    /// Waits until every statement enqueued before this call has been
+    /// committed. Returns the first statement error since the previous
+    /// flush; failed statements are skipped, the rest of their batch is kept.
+    pub async fn flush(&self) -> Result<()> {
+        let (reply, done) = oneshot::channel();
+        self.sender
+            .send(Command::Flush(reply))
+            .await
+            .map_err(|_| DatabaseError::Query("Writer queue has shut down".to_string()))?;
+        done.await
+            .map_err(|_| DatabaseError::Query("Writer queue has shut down".to_string()))?
+    }
+
+    /// Commits everything still queued and stops the background task.
+    pub async fn shutdown(self) -> Result<()> {
+        let result = self.flush().await;
+        drop(self.sender);
+        self.task
+            .await
+            .map_err(|e| DatabaseError::Query(format!("Writer task failed: {}", e)))?;
+        result
+    }
+}
+
+impl Database {
+    /// Starts a background writer holding up to `capacity` queued
+    /// statements; see `WriterQueue`. Must be called inside a tokio runtime.
+    pub fn writer_queue(&self, capacity: usize) -> Result<WriterQueue> {
+        if capacity == 0 {
+            return Err(DatabaseError::InvalidData {
+                message: "Writer queue capacity must be at least 1".to_string(),
+            });
+        }
+
+        let connection = self.connection()?.clone();
+        let encoding = self.encoding();
+        let (sender, receiver) = mpsc::channel(capacity);
+        let task = tokio::spawn(run_writer(connection, encoding, receiver));
+        Ok(WriterQueue {
+            sender,
+            task,
+            capacity,
+        })
+    }
+}
+
+async fn run_writer(connection: DatabaseConnection, encoding: ValueEncoding, mut receiver: mpsc::Receiver<Command>) {
+    // The first failure since the last flush, reported to that flush
+    let mut first_error: Option<DatabaseError> = None;
+    let mut batch = Vec::new();
+
+    while let Some(command) = receiver.recv().await {
+        let mut flushes = Vec::new();
+        let mut next = Some(command);
+        while let Some(command) = next {
+            match command {
+                Command::Write(statement, params) => batch.push((statement, params)),
+                Command::Flush(reply) => flushes.push(reply),
+            }
+            next = if batch.len() < MAX_BATCH { receiver.try_recv().ok() } else { None };
+        }
+
+        if let Err(e) = commit_batch(&connection, encoding, &mut batch, &mut first_error).await {
+            // The batch is dropped rather than retried forever against a broken database
+            tracing::error!(error = %e, "writer queue batch failed");
+            batch.clear();
+            first_error.get_or_insert(e);
+        }
+        for reply in flushes {
+            let _ = reply.send(first_error.take().map_or(Ok(()), Err));
+        }
+    }
+}
+
+async fn commit_batch(
+    connection: &DatabaseConnection,
+    encoding: ValueEncoding,
+    batch: &mut Vec<(String, Vec<SqlValue>)>,
+    first_error: &mut Option<DatabaseError>,
+) -> Result<()> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = connection.begin().await?;
+    for (statement, params) in batch.drain(..) {
+        // A failed statement is rolled back on its own; the transaction stays usable
+        if let Err(e) = sqlx::query_with(&statement, to_arguments(&params, encoding)).execute(&mut *tx).await {
+            tracing::error!(sql = statement, error = %e, "writer queue statement failed");
+            first_error.get_or_insert(e.into());
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
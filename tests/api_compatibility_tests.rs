@@ -1,17 +1,19 @@
 use burncloud_database_core::{
-    Database, DatabaseError, Result,
-    create_database, create_in_memory_database, create_default_database
+    Database, DatabaseError,
+    create_database, create_in_memory_database, create_default_database, get_default_database_path, set_test_mode
 };
 use std::fs;
-use std::path::PathBuf;
 use tempfile::TempDir;
 
 /// API compatibility and regression tests
 /// These tests ensure backward compatibility and API consistency
+/// Tests that open the default database turn on test mode first, so it lives
+/// under the temp dir instead of the user's real data directory
 
 #[tokio::test]
 async fn test_all_database_creation_methods() {
     // Test all database creation methods to ensure API consistency
+    set_test_mode(true);
 
     // Method 1: Database::new() + initialize()
     let temp_dir = TempDir::new().expect("Should create temp directory");
@@ -78,7 +80,7 @@ async fn test_all_database_creation_methods() {
     }
 
     // Clean up default database files
-    if let Ok(default_path) = get_test_default_path() {
+    if let Ok(default_path) = get_default_database_path() {
         let _ = fs::remove_file(&default_path);
         if let Some(parent) = default_path.parent() {
             let _ = fs::remove_dir_all(parent);
@@ -92,7 +94,8 @@ async fn test_all_database_creation_methods() {
 async fn test_database_operation_consistency() {
     // Test that all database types support the same operations consistently
 
-    let databases = create_test_databases().await;
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let databases = create_test_databases(&temp_dir).await;
 
     for (db_type, db) in &databases {
         println!("Testing operations on {} database", db_type);
@@ -115,6 +118,7 @@ async fn test_database_operation_consistency() {
 
         // Test fetch_one
         #[derive(sqlx::FromRow)]
+        #[allow(dead_code)]
         struct ApiTestRow {
             id: i64,
             name: String,
@@ -162,51 +166,34 @@ async fn test_database_operation_consistency() {
 async fn test_error_type_consistency() {
     // Test that all database creation methods return consistent error types
 
-    // Test with invalid paths
-    let invalid_path = "/definitely/invalid/path/test.db";
+    // Test with invalid paths: the parent "directory" is a regular file
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let blocker = temp_dir.path().join("not_a_directory");
+    fs::write(&blocker, b"").expect("Should create file");
+    let invalid_path = blocker.join("path").join("test.db");
 
     // Test Database::new() with invalid path
-    let mut invalid_explicit = Database::new(invalid_path);
+    let mut invalid_explicit = Database::new(&invalid_path);
     let explicit_error = invalid_explicit.initialize().await;
     assert!(explicit_error.is_err());
 
     // Test create_database() with invalid path
-    let convenience_error = create_database(invalid_path).await;
+    let convenience_error = create_database(&invalid_path).await;
     assert!(convenience_error.is_err());
 
-    // Both should return DatabaseError::Connection for invalid paths
+    // Both should return DatabaseError::DirectoryCreation for invalid paths
     match (explicit_error, convenience_error) {
-        (Err(DatabaseError::Connection(_)), Err(DatabaseError::Connection(_))) => {
+        (Err(DatabaseError::DirectoryCreation(_)), Err(DatabaseError::DirectoryCreation(_))) => {
             println!("✓ Consistent error types for invalid paths");
         }
-        (explicit_err, convenience_err) => {
+        (_explicit_err, _convenience_err) => {
             println!("Error type consistency - both should at least be errors");
             // Both should at least be errors, even if types differ slightly
         }
     }
 
-    // Test path resolution errors (platform-specific)
-    #[cfg(target_os = "windows")]
-    {
-        let original_userprofile = std::env::var("USERPROFILE").ok();
-        std::env::remove_var("USERPROFILE");
-
-        let new_default_error = Database::new_default();
-        let new_default_init_error = Database::new_default_initialized().await;
-        let create_default_error = create_default_database().await;
-
-        // All should return PathResolution errors
-        assert!(matches!(new_default_error, Err(DatabaseError::PathResolution(_))));
-        assert!(matches!(new_default_init_error, Err(DatabaseError::PathResolution(_))));
-        assert!(matches!(create_default_error, Err(DatabaseError::PathResolution(_))));
-
-        // Restore environment
-        if let Some(original) = original_userprofile {
-            std::env::set_var("USERPROFILE", original);
-        }
-
-        println!("✓ Consistent error types for path resolution failures");
-    }
+    // Path resolution errors need test mode off, which this binary never
+    // has; see test_mode_tests
 }
 
 #[tokio::test]
@@ -255,16 +242,53 @@ async fn test_backward_compatibility() {
     println!("✓ All backward compatibility patterns work correctly");
 }
 
+#[tokio::test]
+async fn test_is_initialized_tracks_pool_state() {
+    let mut db = Database::new_in_memory();
+    assert!(!db.is_initialized());
+
+    db.initialize().await.expect("Should initialize database");
+    assert!(db.is_initialized());
+
+    // Closing a handle that shares the pool closes it for this one too
+    db.connection().unwrap().clone().close().await;
+    assert!(!db.is_initialized());
+}
+
+#[tokio::test]
+async fn test_reconnect_after_pool_closed() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let mut db = create_database(temp_dir.path().join("reconnect.db")).await.unwrap();
+    db.execute_query("CREATE TABLE t (id INTEGER)").await.unwrap();
+    db.execute_query("INSERT INTO t VALUES (1)").await.unwrap();
+
+    db.connection().unwrap().clone().close().await;
+    assert!(db.execute_query("SELECT 1").await.is_err());
+
+    db.reconnect().await.expect("Should reconnect");
+    let (count,): (i64,) = db.fetch_one("SELECT COUNT(*) FROM t").await.unwrap();
+    assert_eq!(count, 1);
+
+    // In-memory databases come back empty
+    let mut memory_db = create_in_memory_database().await.unwrap();
+    memory_db.execute_query("CREATE TABLE t (id INTEGER)").await.unwrap();
+    memory_db.reconnect().await.expect("Should reconnect");
+    assert!(memory_db.execute_query("SELECT * FROM t").await.is_err());
+    memory_db.execute_query("SELECT 1").await.unwrap();
+}
+
 #[tokio::test]
 async fn test_api_surface_completeness() {
     // Test that all expected APIs are available and functional
 
     // Test Database struct methods
-    let _db = Database::new("test.db");
-    // We can't access the database_path field directly as it's private
+    let db = Database::new("test.db");
+    assert_eq!(db.path(), "test.db");
+    assert!(!db.is_in_memory());
 
-    let _memory_db = Database::new_in_memory();
-    // We can't access the database_path field directly as it's private
+    let memory_db = Database::new_in_memory();
+    assert_eq!(memory_db.path(), ":memory:");
+    assert!(memory_db.is_in_memory());
 
     // Test that new APIs are available
     let _default_result = Database::new_default();
@@ -286,7 +310,8 @@ async fn test_api_surface_completeness() {
 async fn test_database_connection_consistency() {
     // Test that DatabaseConnection behaves consistently across all database types
 
-    let databases = create_test_databases().await;
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let databases = create_test_databases(&temp_dir).await;
 
     for (db_type, db) in &databases {
         if let Ok(connection) = db.connection() {
@@ -343,9 +368,44 @@ async fn test_connection_sharing_behavior() {
     }
 }
 
+#[tokio::test]
+async fn test_named_in_memory_databases_share_data_by_name() {
+    let mut first = Database::new_in_memory_named("api_compat_shared");
+    let mut second = Database::new_in_memory_named("api_compat_shared");
+    let mut other = Database::new_in_memory_named("api_compat_other");
+    for db in [&mut first, &mut second, &mut other] {
+        db.initialize().await.expect("Should initialize named in-memory database");
+    }
+    assert!(first.is_in_memory());
+
+    first.execute_query("CREATE TABLE shared (id INTEGER)").await.unwrap();
+    second.execute_query("INSERT INTO shared (id) VALUES (1)").await.unwrap();
+    let (count,): (i64,) = first.fetch_one("SELECT COUNT(*) FROM shared").await.unwrap();
+    assert_eq!(count, 1, "Handles with the same name should see each other's writes");
+    assert!(!other.table_exists("shared").await.unwrap(), "A different name is a different database");
+
+    let mut invalid = Database::new_in_memory_named("bad?name");
+    assert!(matches!(invalid.initialize().await, Err(DatabaseError::InvalidData { .. })));
+
+    for db in [first, second, other] {
+        let _ = db.close().await;
+    }
+}
+
+#[test]
+fn test_types_module_is_public() {
+    // Shared types are reachable both from the crate root and through `types`
+    let options = burncloud_database_core::QueryOptions::default();
+    assert_eq!(options, burncloud_database_core::types::QueryOptions::default());
+    assert_eq!(options.order_direction, burncloud_database_core::types::OrderDirection::Asc);
+    assert!(burncloud_database_core::types::QueryResult::default().rows.is_empty());
+}
+
 // Helper functions
 
-async fn create_test_databases() -> Vec<(String, Database)> {
+// The file database lives in `temp_dir`, which must outlive it.
+async fn create_test_databases(temp_dir: &TempDir) -> Vec<(String, Database)> {
+    set_test_mode(true);
     let mut databases = vec![];
 
     // In-memory database (always works)
@@ -354,11 +414,9 @@ async fn create_test_databases() -> Vec<(String, Database)> {
     }
 
     // Temporary file database
-    if let Ok(temp_dir) = TempDir::new() {
-        let temp_path = temp_dir.path().join("temp_test.db");
-        if let Ok(temp_db) = create_database(&temp_path).await {
-            databases.push(("temporary_file".to_string(), temp_db));
-        }
+    let temp_path = temp_dir.path().join("temp_test.db");
+    if let Ok(temp_db) = create_database(&temp_path).await {
+        databases.push(("temporary_file".to_string(), temp_db));
     }
 
     // Default location database (may fail in some environments)
@@ -376,29 +434,10 @@ async fn cleanup_test_databases(databases: Vec<(String, Database)>) {
     }
 
     // Clean up default location files
-    if let Ok(default_path) = get_test_default_path() {
+    if let Ok(default_path) = get_default_database_path() {
         let _ = fs::remove_file(&default_path);
         if let Some(parent) = default_path.parent() {
             let _ = fs::remove_dir_all(parent);
         }
     }
 }
-
-fn get_test_default_path() -> Result<PathBuf> {
-    use burncloud_database_core::DatabaseError;
-
-    let db_dir = if cfg!(target_os = "windows") {
-        let user_profile = std::env::var("USERPROFILE")
-            .map_err(|e| DatabaseError::PathResolution(format!("USERPROFILE not found: {}", e)))?;
-        PathBuf::from(user_profile)
-            .join("AppData")
-            .join("Local")
-            .join("BurnCloud")
-    } else {
-        dirs::home_dir()
-            .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
-            .join(".burncloud")
-    };
-
-    Ok(db_dir.join("data.db"))
-}
\ No newline at end of file
@@ -0,0 +1,572 @@
+use burncloud_database_core::{
+    normalize_sql, sqlx, Database, DatabaseConfig, DatabaseConnection, DatabaseError, JournalMode, PoolConfig,
+    RetryPolicy, SqlValue,
+};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+// Configuration tests
+// These tests cover the builder options applied when a database is initialized
+
+async fn initialize(mut db: Database) -> Database {
+    db.initialize().await.expect("Should initialize database");
+    db
+}
+
+fn touch(path: &Path) {
+    std::fs::File::create(path).expect("Should create database file");
+}
+
+#[tokio::test]
+async fn test_test_before_acquire_option() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("ping.db");
+    touch(&db_path);
+
+    let db = initialize(Database::new(&db_path).test_before_acquire(true)).await;
+    assert!(db.connection().unwrap().pool().options().get_test_before_acquire());
+
+    db.execute_query("CREATE TABLE items (id INTEGER PRIMARY KEY)").await.unwrap();
+    for _ in 0..5 {
+        db.execute_query("INSERT INTO items DEFAULT VALUES").await.unwrap();
+    }
+    let (count,): (i64,) = db.fetch_one("SELECT COUNT(*) FROM items").await.unwrap();
+    assert_eq!(count, 5);
+    db.close().await.unwrap();
+
+    let db = initialize(Database::new(&db_path).test_before_acquire(false)).await;
+    assert!(!db.connection().unwrap().pool().options().get_test_before_acquire());
+    let (count,): (i64,) = db.fetch_one("SELECT COUNT(*) FROM items").await.unwrap();
+    assert_eq!(count, 5);
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_test_before_acquire_defaults_to_enabled() {
+    let db = initialize(Database::new_in_memory()).await;
+    assert!(db.connection().unwrap().pool().options().get_test_before_acquire());
+    db.close().await.unwrap();
+}
+
+async fn create_wide_table(db: &Database, columns: usize) -> Vec<String> {
+    let names: Vec<String> = (0..columns).map(|i| format!("c{}", i)).collect();
+    db.execute_query(&format!("CREATE TABLE wide ({})", names.join(", ")))
+        .await
+        .expect("Should create wide table");
+    names
+}
+
+#[tokio::test]
+async fn test_max_variable_number_option() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("wide.db");
+    touch(&db_path);
+
+    let db = initialize(Database::new(&db_path).max_variable_number(2000)).await;
+    let names = create_wide_table(&db, 1200).await;
+    let columns: Vec<&str> = names.iter().map(String::as_str).collect();
+    let row: Vec<SqlValue> = (0..1200).map(SqlValue::from).collect();
+
+    let inserted = db
+        .insert_many("wide", &columns, &[row.clone(), row.clone()])
+        .await
+        .expect("A 1200-parameter row should fit after raising the limit");
+    assert_eq!(inserted, 2);
+    let (last,): (i64,) = db.fetch_one("SELECT c1199 FROM wide LIMIT 1").await.unwrap();
+    assert_eq!(last, 1199);
+    db.close().await.unwrap();
+
+    // A lower limit is enforced by SQLite itself
+    let db = initialize(Database::new(&db_path).max_variable_number(500)).await;
+    let too_wide = db.insert_many("wide", &columns, &[row]).await;
+    assert!(matches!(too_wide, Err(DatabaseError::Connection(_))));
+    db.close().await.unwrap();
+
+    let mut zero = Database::new_in_memory().max_variable_number(0);
+    assert!(matches!(zero.initialize().await, Err(DatabaseError::InvalidData { .. })));
+}
+
+#[tokio::test]
+async fn test_with_pool_size_allows_fifty_concurrent_queries() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("pool.db");
+    touch(&db_path);
+
+    let db = initialize(Database::with_pool_size(&db_path, 50)).await;
+    let connection = db.connection().unwrap().clone();
+    assert_eq!(connection.pool().options().get_max_connections(), 50);
+
+    let mut tasks = tokio::task::JoinSet::new();
+    let barrier = std::sync::Arc::new(tokio::sync::Barrier::new(50));
+    for i in 0..50i64 {
+        let connection = connection.clone();
+        let barrier = barrier.clone();
+        tasks.spawn(async move {
+            // Every task holds its connection until all 50 are checked out
+            let mut conn = connection.acquire().await?;
+            barrier.wait().await;
+            let (value,): (i64,) = sqlx::query_as("SELECT ?").bind(i).fetch_one(&mut *conn).await?;
+            Ok::<_, DatabaseError>(value)
+        });
+    }
+
+    let mut total = 0;
+    while let Some(result) = tasks.join_next().await {
+        total += result.unwrap().expect("Query should succeed");
+    }
+    assert_eq!(total, (0..50).sum::<i64>());
+    db.close().await.unwrap();
+
+    let default_db = initialize(Database::new(&db_path)).await;
+    assert_eq!(default_db.connection().unwrap().pool().options().get_max_connections(), 10);
+    default_db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pool_stats_separate_acquire_and_execute_time() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("latency.db");
+    touch(&db_path);
+
+    // One connection shared by eight tasks: most of the time is spent waiting for it
+    let db = std::sync::Arc::new(initialize(Database::with_pool_size(&db_path, 1)).await);
+    let baseline = db.pool_stats().unwrap();
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for _ in 0..8 {
+        let db = db.clone();
+        tasks.spawn(async move {
+            db.fetch_one::<(i64,)>(
+                "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 100000)
+                 SELECT SUM(n) FROM seq",
+            )
+            .await
+        });
+    }
+    while let Some(result) = tasks.join_next().await {
+        result.unwrap().expect("Query should succeed");
+    }
+
+    let stats = db.pool_stats().unwrap();
+    assert_eq!(stats.executions - baseline.executions, 8);
+    assert!(stats.acquires - baseline.acquires >= 8);
+    let acquire_time = stats.total_acquire_time - baseline.total_acquire_time;
+    let execute_time = stats.total_execute_time - baseline.total_execute_time;
+    println!("acquire: {:?}, execute: {:?}", acquire_time, execute_time);
+    assert!(
+        acquire_time > execute_time,
+        "Waiting for the single connection ({:?}) should dominate running the queries ({:?})",
+        acquire_time,
+        execute_time
+    );
+
+    std::sync::Arc::try_unwrap(db).ok().unwrap().close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pool_stats_report_idle_connections() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("idle.db");
+    touch(&db_path);
+
+    let uninitialized = Database::with_pool_size(&db_path, 4);
+    assert!(matches!(uninitialized.pool_stats(), Err(DatabaseError::NotInitialized)));
+
+    let db = initialize(Database::with_pool_size(&db_path, 4)).await;
+    let connection = db.connection().unwrap();
+    assert_eq!(db.pool_stats().unwrap().max_connections, 4);
+
+    let first = connection.acquire().await.unwrap();
+    let second = connection.acquire().await.unwrap();
+    let stats = connection.stats();
+    assert_eq!(stats.size, 2);
+    assert_eq!(stats.idle, 0, "Both connections are checked out");
+
+    drop(first);
+    drop(second);
+    // Released connections are returned to the pool asynchronously
+    for _ in 0..50 {
+        if connection.stats().idle == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(db.pool_stats().unwrap().idle, 2);
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_shares_pool_with() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("shared.db");
+    touch(&db_path);
+
+    let db = initialize(Database::new(&db_path)).await;
+    let shared = Database::from_connection(db.connection().unwrap().clone());
+    assert!(db.shares_pool_with(&shared));
+    assert!(shared.shares_pool_with(&db));
+
+    shared.execute_query("CREATE TABLE items (id INTEGER PRIMARY KEY)").await.unwrap();
+    let (count,): (i64,) = db.fetch_one("SELECT COUNT(*) FROM items").await.unwrap();
+    assert_eq!(count, 0);
+
+    let independent = initialize(Database::new(&db_path)).await;
+    assert!(!db.shares_pool_with(&independent), "Separately initialized databases have their own pools");
+    assert!(!db.shares_pool_with(&Database::new(&db_path)));
+
+    independent.close().await.unwrap();
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pool_size_must_be_positive() {
+    let mut db = Database::with_pool_size(":memory:", 0);
+    assert!(matches!(db.initialize().await, Err(DatabaseError::InvalidData { .. })));
+
+    let connection = DatabaseConnection::new_with_options("sqlite::memory:", 0).await;
+    assert!(matches!(connection, Err(DatabaseError::InvalidData { .. })));
+
+    let connection = DatabaseConnection::new_with_options("sqlite::memory:", 3)
+        .await
+        .expect("Should connect");
+    assert_eq!(connection.pool().options().get_max_connections(), 3);
+    connection.close().await;
+}
+
+#[tokio::test]
+async fn test_pool_config_acquire_timeout() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("timeout.db");
+    touch(&db_path);
+
+    let config = PoolConfig {
+        max_connections: 2,
+        acquire_timeout: Duration::from_millis(100),
+        idle_timeout: None,
+        ..PoolConfig::default()
+    };
+    let db = initialize(Database::new_with_pool_config(&db_path, config.clone())).await;
+    let options = db.connection().unwrap().pool().options();
+    assert_eq!(options.get_max_connections(), 2);
+    assert_eq!(options.get_acquire_timeout(), Duration::from_millis(100));
+    assert_eq!(options.get_idle_timeout(), None);
+    assert_eq!(options.get_max_lifetime(), config.max_lifetime);
+
+    let _first = db.connection().unwrap().acquire().await.unwrap();
+    let _second = db.connection().unwrap().acquire().await.unwrap();
+
+    let started = Instant::now();
+    let exhausted = db.execute_query("SELECT 1").await;
+    assert!(matches!(exhausted, Err(DatabaseError::Connection(sqlx::Error::PoolTimedOut))));
+    assert!(started.elapsed() < Duration::from_secs(5), "Acquire should give up after the configured timeout");
+}
+
+#[tokio::test]
+async fn test_pool_config_rejects_inconsistent_sizes() {
+    let config = PoolConfig {
+        max_connections: 2,
+        min_connections: 3,
+        ..PoolConfig::default()
+    };
+    let mut db = Database::new_with_pool_config(":memory:", config);
+    assert!(matches!(db.initialize().await, Err(DatabaseError::InvalidData { .. })));
+}
+
+#[tokio::test]
+async fn test_new_with_journal_mode_wal() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("wal.db");
+    touch(&db_path);
+
+    let db = initialize(Database::new_with_journal_mode(&db_path, JournalMode::Wal)).await;
+    let (mode,): (String,) = db.fetch_one("PRAGMA journal_mode").await.unwrap();
+    assert_eq!(mode, "wal");
+    let (synchronous,): (i64,) = db.fetch_one("PRAGMA synchronous").await.unwrap();
+    assert_eq!(synchronous, 1, "WAL databases should use synchronous = NORMAL");
+    db.close().await.unwrap();
+
+    let db = initialize(Database::new_with_journal_mode(&db_path, JournalMode::Truncate)).await;
+    let (mode,): (String,) = db.fetch_one("PRAGMA journal_mode").await.unwrap();
+    assert_eq!(mode, "truncate");
+    db.close().await.unwrap();
+
+    // WAL is not available in memory and is skipped rather than failing
+    let db = initialize(Database::new_with_journal_mode(":memory:", JournalMode::Wal)).await;
+    let (mode,): (String,) = db.fetch_one("PRAGMA journal_mode").await.unwrap();
+    assert_eq!(mode, "memory");
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_busy_timeout_lets_concurrent_writers_succeed() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("busy.db");
+    touch(&db_path);
+
+    let db = initialize(Database::with_busy_timeout(&db_path, 10_000)).await;
+    db.execute_query("CREATE TABLE writes (writer INTEGER, n INTEGER)").await.unwrap();
+    let connection = db.connection().unwrap().clone();
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for writer in 0..10i64 {
+        let connection = connection.clone();
+        tasks.spawn(async move {
+            let mut conn = connection.acquire().await?;
+            let (timeout,): (i64,) = sqlx::query_as("PRAGMA busy_timeout").fetch_one(&mut *conn).await?;
+            assert_eq!(timeout, 10_000, "Every pooled connection should get the busy timeout");
+
+            // Hold the write lock briefly so the other writers have to wait for it
+            sqlx::query("BEGIN IMMEDIATE").execute(&mut *conn).await?;
+            for n in 0..20i64 {
+                sqlx::query("INSERT INTO writes VALUES (?, ?)")
+                    .bind(writer)
+                    .bind(n)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            sqlx::query("COMMIT").execute(&mut *conn).await?;
+            Ok::<_, DatabaseError>(())
+        });
+    }
+
+    while let Some(result) = tasks.join_next().await {
+        result.unwrap().expect("Writer should wait for the lock instead of failing");
+    }
+    let (count,): (i64,) = db.fetch_one("SELECT COUNT(*) FROM writes").await.unwrap();
+    assert_eq!(count, 200);
+    db.close().await.unwrap();
+
+    let db = initialize(Database::new(&db_path)).await;
+    let (timeout,): (i64,) = db.fetch_one("PRAGMA busy_timeout").await.unwrap();
+    assert_eq!(timeout, 5000);
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_retry_policy_waits_out_a_locked_database() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("retry.db");
+
+    let holder = initialize(Database::new(&db_path)).await;
+    holder.execute_query("CREATE TABLE writes (n INTEGER)").await.unwrap();
+
+    // No busy timeout, so every contended write fails at once unless retried
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        backoff: Duration::from_millis(100),
+    };
+    let retrying = initialize(Database::with_busy_timeout(&db_path, 0).retry_policy(policy)).await;
+    let plain = initialize(Database::with_busy_timeout(&db_path, 0)).await;
+
+    let mut lock = holder.connection().unwrap().acquire().await.unwrap();
+    sqlx::query("BEGIN IMMEDIATE").execute(&mut *lock).await.unwrap();
+    let release = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        sqlx::query("COMMIT").execute(&mut *lock).await.unwrap();
+    });
+
+    let failed = plain.execute_query("INSERT INTO writes VALUES (0)").await;
+    assert!(matches!(failed, Err(DatabaseError::Connection(_))), "{:?}", failed);
+    // Attempts at 0ms and 100ms hit the lock; the one at 300ms gets through
+    retrying.execute_query("INSERT INTO writes VALUES (1)").await.unwrap();
+    assert_eq!(retrying.insert("INSERT INTO writes VALUES (?)", &[SqlValue::Integer(2)]).await.unwrap(), 2);
+    release.await.unwrap();
+
+    let mut lock = holder.connection().unwrap().acquire().await.unwrap();
+    sqlx::query("BEGIN IMMEDIATE").execute(&mut *lock).await.unwrap();
+    let release = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        sqlx::query("COMMIT").execute(&mut *lock).await.unwrap();
+    });
+    retrying
+        .execute_query_with_params("INSERT INTO writes VALUES (?)", vec!["3".to_string()])
+        .await
+        .expect("Text-parameter writes should be retried too");
+    release.await.unwrap();
+
+    // Errors other than busy are not retried
+    let started = Instant::now();
+    assert!(retrying.execute_query("INSERT INTO missing VALUES (1)").await.is_err());
+    assert!(started.elapsed() < Duration::from_millis(100));
+}
+
+async fn create_parent_child_tables(db: &Database) {
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY)").await.unwrap();
+    db.execute_query("CREATE TABLE deployments (id INTEGER PRIMARY KEY, model_id INTEGER REFERENCES models(id))")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_foreign_keys_enforced_on_every_connection() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("fk.db");
+    touch(&db_path);
+
+    let db = initialize(Database::new(&db_path)).await;
+    create_parent_child_tables(&db).await;
+
+    let connection = db.connection().unwrap();
+    let mut held = Vec::new();
+    for _ in 0..10 {
+        let mut conn = connection.acquire().await.unwrap();
+        let (enabled,): (bool,) = sqlx::query_as("PRAGMA foreign_keys").fetch_one(&mut *conn).await.unwrap();
+        assert!(enabled, "Every pooled connection should enforce foreign keys");
+        held.push(conn);
+    }
+    drop(held);
+
+    let orphan = db.execute_query("INSERT INTO deployments (model_id) VALUES (42)").await;
+    assert!(matches!(orphan, Err(DatabaseError::ConstraintViolation { .. })));
+    db.close().await.unwrap();
+
+    let db = initialize(Database::with_foreign_keys(&db_path, false)).await;
+    db.execute_query("INSERT INTO deployments (model_id) VALUES (42)")
+        .await
+        .expect("Orphans are allowed when foreign keys are off");
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_register_collation() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("collation.db");
+    touch(&db_path);
+
+    let db = initialize(
+        Database::new(&db_path)
+            .register_collation("case_insensitive", |a, b| a.to_lowercase().cmp(&b.to_lowercase()))
+            .register_collation("by_length", |a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b))),
+    )
+    .await;
+    db.execute_query("CREATE TABLE models (name TEXT)").await.unwrap();
+    db.execute_query("INSERT INTO models VALUES ('mistral'), ('Llama'), ('alpaca'), ('Qwen'), ('bert')")
+        .await
+        .unwrap();
+
+    // Hold connections so the queries below run on several different ones
+    let connection = db.connection().unwrap();
+    let _held = [connection.acquire().await.unwrap(), connection.acquire().await.unwrap()];
+
+    let names: Vec<(String,)> = db
+        .fetch_all("SELECT name FROM models ORDER BY name COLLATE case_insensitive")
+        .await
+        .unwrap();
+    let names: Vec<String> = names.into_iter().map(|(name,)| name).collect();
+    assert_eq!(names, vec!["alpaca", "bert", "Llama", "mistral", "Qwen"]);
+
+    let mut conn = connection.acquire().await.unwrap();
+    let by_length: Vec<(String,)> = sqlx::query_as("SELECT name FROM models ORDER BY name COLLATE by_length")
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap();
+    let by_length: Vec<String> = by_length.into_iter().map(|(name,)| name).collect();
+    assert_eq!(by_length, vec!["Qwen", "bert", "Llama", "alpaca", "mistral"]);
+}
+
+#[tokio::test]
+async fn test_self_check_reports_configured_settings() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let wal_path = temp_dir.path().join("wal.db");
+    touch(&wal_path);
+
+    let db = initialize(Database::new_with_journal_mode(&wal_path, JournalMode::Wal)).await;
+    let report = db.self_check().await.unwrap();
+    assert!(report.is_ok(), "{:?}", report.mismatches().collect::<Vec<_>>());
+    assert_eq!(report.get("journal_mode").unwrap().actual, "wal");
+    assert_eq!(report.get("synchronous").unwrap().actual, "NORMAL");
+    assert_eq!(report.get("foreign_keys").unwrap().actual, "true");
+    assert_eq!(report.get("busy_timeout").unwrap().actual, "5000");
+    assert_eq!(report.get("max_connections").unwrap().actual, "10");
+
+    let plain_path = temp_dir.path().join("plain.db");
+    touch(&plain_path);
+    let db = initialize(Database::with_foreign_keys(&plain_path, false)).await;
+    let report = db.self_check().await.unwrap();
+    assert!(report.is_ok(), "{:?}", report.mismatches().collect::<Vec<_>>());
+    assert_eq!(report.get("foreign_keys").unwrap().actual, "false");
+    assert_eq!(report.get("synchronous").unwrap().actual, "FULL");
+    assert!(report.get("journal_mode").is_none());
+
+    let db = initialize(Database::with_pool_size(":memory:", 3)).await;
+    let report = db.self_check().await.unwrap();
+    assert!(report.is_ok(), "{:?}", report.mismatches().collect::<Vec<_>>());
+    assert_eq!(report.get("max_connections").unwrap().actual, "3");
+}
+
+#[tokio::test]
+async fn test_new_existing_requires_the_file() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("missing").join("app.db");
+
+    let mut db = Database::new_existing(&db_path);
+    assert!(matches!(db.initialize().await, Err(DatabaseError::Connection(_))));
+    assert!(!db_path.exists());
+    assert!(!db_path.parent().unwrap().exists());
+
+    // The default mode creates the file, after which new_existing opens it
+    let db = initialize(Database::new(&db_path)).await;
+    db.close().await.unwrap();
+    assert!(db_path.exists());
+
+    let db = initialize(Database::new_existing(&db_path)).await;
+    db.ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_slow_query_callback_fires_only_for_slow_queries() {
+    let slow_queries = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = slow_queries.clone();
+    let db = initialize(
+        Database::with_slow_query_threshold(":memory:", Duration::from_millis(50))
+            .on_slow_query(move |sql, elapsed| recorded.lock().unwrap().push((sql.to_string(), elapsed))),
+    )
+    .await;
+
+    db.execute_query("CREATE TABLE t (id INTEGER)").await.unwrap();
+    let _: (i64,) = db.fetch_one("SELECT 1").await.unwrap();
+    assert!(slow_queries.lock().unwrap().is_empty(), "Fast queries should not be reported");
+
+    let slow = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 3000000) \
+                SELECT COUNT(*) FROM n";
+    let (count,): (i64,) = db.fetch_one(slow).await.unwrap();
+    assert_eq!(count, 3_000_000);
+
+    let slow_queries = slow_queries.lock().unwrap();
+    assert_eq!(slow_queries.len(), 1);
+    assert_eq!(slow_queries[0].0, normalize_sql(slow), "Slow queries should be reported normalized");
+    assert!(slow_queries[0].0.contains("i < ?"));
+    assert!(slow_queries[0].1 >= Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn test_connect_with_config() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("configured.db");
+    let config = DatabaseConfig {
+        database: db_path.to_str().unwrap().to_string(),
+        host: Some("ignored.example.com".to_string()),
+        port: Some(5432),
+        pool_size: 3,
+        timeout: Duration::from_millis(200),
+        ..DatabaseConfig::default()
+    };
+
+    let db = Database::connect_with_config(&config).await.unwrap();
+    db.execute_query("CREATE TABLE t (id INTEGER)").await.unwrap();
+    assert!(db_path.exists());
+    assert_eq!(db.pool_stats().unwrap().max_connections, 3);
+
+    // The fourth checkout waits `timeout` and then fails
+    let connection = db.connection().unwrap();
+    let _held = [
+        connection.acquire().await.unwrap(),
+        connection.acquire().await.unwrap(),
+        connection.acquire().await.unwrap(),
+    ];
+    let exhausted = connection.acquire().await;
+    assert!(matches!(exhausted, Err(DatabaseError::Connection(sqlx::Error::PoolTimedOut))));
+}
@@ -0,0 +1,50 @@
+use burncloud_database_core::{
+    create_database, create_default_database, get_default_database_path, set_test_mode, DatabaseError,
+};
+use tempfile::TempDir;
+
+// Default database swap tests
+// These enable the process-wide test mode, so they run in their own test binary
+
+#[tokio::test]
+async fn test_swap_default_with_replaces_contents() {
+    set_test_mode(true);
+    let default_path = get_default_database_path().expect("Should resolve default path");
+    std::fs::create_dir_all(default_path.parent().unwrap()).unwrap();
+    std::fs::File::create(&default_path).expect("Should create default database file");
+
+    let mut db = create_default_database().await.expect("Should open default database");
+    db.execute_query("CREATE TABLE models (name TEXT)").await.unwrap();
+    db.execute_query("INSERT INTO models VALUES ('old')").await.unwrap();
+
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let new_file = temp_dir.path().join("refresh.db");
+    std::fs::File::create(&new_file).unwrap();
+    let refreshed = create_database(&new_file).await.unwrap();
+    refreshed.execute_query("CREATE TABLE models (name TEXT)").await.unwrap();
+    refreshed.execute_query("INSERT INTO models VALUES ('new'), ('newer')").await.unwrap();
+    refreshed.close().await.unwrap();
+
+    db.swap_default_with(&new_file).await.expect("Swap should succeed");
+
+    let names: Vec<(String,)> = db.fetch_all("SELECT name FROM models ORDER BY name").await.unwrap();
+    assert_eq!(names, vec![("new".to_string(),), ("newer".to_string(),)]);
+    assert!(!new_file.exists(), "The new file should have been moved into place");
+
+    // Only the default database can be swapped
+    let other_path = temp_dir.path().join("other.db");
+    std::fs::File::create(&other_path).unwrap();
+    let mut other = create_database(&other_path).await.unwrap();
+    let result = other.swap_default_with(&other_path).await;
+    assert!(matches!(result, Err(DatabaseError::InvalidData { .. })));
+
+    // A missing replacement leaves the current database usable
+    let missing = db.swap_default_with(&temp_dir.path().join("missing.db")).await;
+    assert!(matches!(missing, Err(DatabaseError::Io(_))));
+    let (count,): (i64,) = db.fetch_one("SELECT COUNT(*) FROM models").await.unwrap();
+    assert_eq!(count, 2);
+
+    db.close().await.unwrap();
+    other.close().await.unwrap();
+    let _ = std::fs::remove_dir_all(default_path.parent().unwrap());
+}
@@ -0,0 +1,36 @@
+use burncloud_database_core::{create_default_database, get_default_database_path, set_test_mode};
+
+// Directory recovery tests
+// These enable the process-wide test mode, so they run in their own test binary
+
+#[tokio::test]
+async fn test_write_recreates_deleted_default_directory() {
+    set_test_mode(true);
+    let default_path = get_default_database_path().expect("Should resolve default path");
+    let default_dir = default_path.parent().unwrap().to_path_buf();
+    std::fs::create_dir_all(&default_dir).unwrap();
+    std::fs::File::create(&default_path).expect("Should create default database file");
+
+    let db = create_default_database().await.expect("Should open default database");
+    db.execute_query("CREATE TABLE events (id INTEGER PRIMARY KEY)").await.unwrap();
+    db.execute_query("INSERT INTO events DEFAULT VALUES").await.unwrap();
+
+    std::fs::remove_dir_all(&default_dir).expect("Should delete the directory");
+    assert!(!default_dir.exists());
+
+    // The database file went with the directory, so writes continue on a new, empty file
+    db.execute_query("CREATE TABLE audit (id INTEGER PRIMARY KEY)")
+        .await
+        .expect("The write should recover by recreating the directory");
+    assert!(default_dir.exists(), "The directory should have been recreated");
+    assert!(default_path.exists());
+
+    db.execute_query_with_params("INSERT INTO audit (id) VALUES (?)", vec!["10".to_string()])
+        .await
+        .unwrap();
+    let (count,): (i64,) = db.fetch_one("SELECT COUNT(*) FROM audit").await.unwrap();
+    assert_eq!(count, 1);
+
+    db.close().await.unwrap();
+    let _ = std::fs::remove_dir_all(&default_dir);
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "sqlcipher")]
+
+use burncloud_database_core::{Database, DatabaseError};
+use tempfile::TempDir;
+
+// Encryption tests
+// These need SQLCipher and only run with `--features sqlcipher`
+
+#[tokio::test]
+async fn test_encrypted_database_requires_the_right_key() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let path = temp_dir.path().join("secrets.db");
+    std::fs::File::create(&path).unwrap();
+
+    let mut db = Database::new_encrypted(&path, "correct horse");
+    db.initialize().await.expect("Should create encrypted database");
+    db.execute_query("CREATE TABLE api_keys (id INTEGER PRIMARY KEY, key TEXT NOT NULL)").await.unwrap();
+    db.execute_query("INSERT INTO api_keys (key) VALUES ('sk-123')").await.unwrap();
+    db.close().await.unwrap();
+
+    let plain = std::fs::read(&path).unwrap();
+    assert!(!plain.windows(6).any(|window| window == b"sk-123"), "The file should not contain plaintext");
+
+    let mut db = Database::new_encrypted(&path, "correct horse");
+    db.initialize().await.expect("The right key should open the database");
+    let (key,): (String,) = db.fetch_one("SELECT key FROM api_keys").await.unwrap();
+    assert_eq!(key, "sk-123");
+    db.close().await.unwrap();
+
+    let mut db = Database::new_encrypted(&path, "wrong key");
+    assert!(matches!(db.initialize().await, Err(DatabaseError::Connection(_))));
+}
@@ -0,0 +1,38 @@
+use burncloud_database_core::{create_default_database, get_default_database_path, set_test_mode};
+use tempfile::TempDir;
+
+// Environment override tests
+// These change BURNCLOUD_DATABASE_PATH for the whole process, so they run in their own test binary
+
+#[tokio::test]
+async fn test_database_path_env_var_overrides_default() {
+    let platform_default = get_default_database_path().expect("Should resolve default path");
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let override_path = temp_dir.path().join("volume").join("app.db");
+
+    std::env::set_var("BURNCLOUD_DATABASE_PATH", "");
+    assert_eq!(get_default_database_path().unwrap(), platform_default, "An empty value should be ignored");
+
+    std::env::set_var("BURNCLOUD_DATABASE_PATH", &override_path);
+    assert_eq!(get_default_database_path().unwrap(), override_path);
+
+    std::fs::create_dir_all(override_path.parent().unwrap()).unwrap();
+    std::fs::File::create(&override_path).unwrap();
+    let db = create_default_database().await.expect("Should open the overridden database");
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY)").await.unwrap();
+    db.close().await.unwrap();
+    assert!(
+        std::fs::metadata(&override_path).unwrap().len() > 0,
+        "The database should be written at the overridden location"
+    );
+
+    // Test mode wins over the override, which may name a production database
+    set_test_mode(true);
+    let test_path = get_default_database_path().unwrap();
+    set_test_mode(false);
+    assert_ne!(test_path, override_path);
+    assert!(test_path.starts_with(std::env::temp_dir()), "{:?} should be under the temp directory", test_path);
+
+    std::env::remove_var("BURNCLOUD_DATABASE_PATH");
+    assert_eq!(get_default_database_path().unwrap(), platform_default);
+}
@@ -1,9 +1,12 @@
-use burncloud_database_core::{Database, DatabaseError, Result, create_default_database};
+use burncloud_database_core::{
+    Database, DatabaseError, DatabaseResult, Result, create_default_database, get_default_database_path, set_test_mode,
+};
 use std::fs;
-use std::path::PathBuf;
 
 /// Comprehensive error handling and edge case tests
 /// These tests ensure robust error handling and graceful degradation
+/// Tests that open the default database turn on test mode first, so it lives
+/// under the temp dir instead of the user's real data directory
 
 #[test]
 fn test_all_error_variants() {
@@ -69,9 +72,63 @@ async fn test_uninitialized_operations(db: &Database) {
     println!("✓ All operations correctly failed on uninitialized database");
 }
 
+#[tokio::test]
+async fn test_ping_reports_connection_state() {
+    let uninitialized = Database::new_in_memory();
+    assert!(matches!(uninitialized.ping().await, Err(DatabaseError::NotInitialized)));
+
+    let mut db = Database::new_in_memory();
+    db.initialize().await.expect("Should initialize database");
+    db.ping().await.expect("An initialized database should answer");
+
+    db.connection().unwrap().clone().close().await;
+    assert!(matches!(db.ping().await, Err(DatabaseError::Connection(_))), "A closed pool should fail the ping");
+}
+
+#[tokio::test]
+async fn test_constraint_violations_are_distinguished() {
+    let mut db = Database::new_in_memory();
+    db.initialize().await.expect("Should initialize database");
+    db.execute_query("CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL UNIQUE)")
+        .await
+        .unwrap();
+    db.execute_query("INSERT INTO users VALUES (1, 'a@example.com')").await.unwrap();
+
+    match db.execute_query("INSERT INTO users VALUES (1, 'b@example.com')").await {
+        Err(DatabaseError::ConstraintViolation { constraint, message }) => {
+            assert_eq!(constraint.as_deref(), Some("users.id"));
+            assert!(message.contains("UNIQUE constraint failed"), "{}", message);
+        }
+        other => panic!("Expected a constraint violation, got {:?}", other),
+    }
+    let duplicate_email = db.execute_query("INSERT INTO users VALUES (2, 'a@example.com')").await;
+    assert!(matches!(
+        duplicate_email,
+        Err(DatabaseError::ConstraintViolation { constraint: Some(ref c), .. }) if c == "users.email"
+    ));
+
+    // Other database errors are unchanged
+    let missing_table = db.execute_query("INSERT INTO missing VALUES (1)").await;
+    assert!(matches!(missing_table, Err(DatabaseError::Connection(_))));
+}
+
+#[tokio::test]
+async fn test_fetch_one_without_rows_is_not_found() {
+    let mut db = Database::new_in_memory();
+    db.initialize().await.expect("Should initialize database");
+    db.execute_query("CREATE TABLE users (id INTEGER PRIMARY KEY)").await.unwrap();
+
+    let missing = db.fetch_one::<(i64,)>("SELECT id FROM users").await;
+    assert!(matches!(missing, Err(DatabaseError::NotFound)), "{:?}", missing);
+
+    let optional = db.fetch_optional::<(i64,)>("SELECT id FROM users").await;
+    assert!(matches!(optional, Ok(None)));
+}
+
 #[tokio::test]
 async fn test_invalid_sql_operations() {
     // Test error handling for invalid SQL operations
+    set_test_mode(true);
     let db_result = create_default_database().await;
 
     if let Ok(db) = db_result {
@@ -99,7 +156,7 @@ async fn test_invalid_sql_operations() {
         let _ = db.close().await;
 
         // Clean up
-        if let Ok(default_path) = get_test_default_path() {
+        if let Ok(default_path) = get_default_database_path() {
             let _ = fs::remove_file(&default_path);
             if let Some(parent) = default_path.parent() {
                 let _ = fs::remove_dir_all(parent);
@@ -113,6 +170,7 @@ async fn test_invalid_sql_operations() {
 #[tokio::test]
 async fn test_connection_pool_exhaustion() {
     // Test behavior when connection pool is exhausted
+    set_test_mode(true);
     let db_result = create_default_database().await;
 
     if let Ok(db) = db_result {
@@ -127,7 +185,7 @@ async fn test_connection_pool_exhaustion() {
                 let result = sqlx::query(&format!("SELECT {} as operation_id", i))
                     .execute(connection.pool())
                     .await
-                    .map_err(|e| burncloud_database_core::DatabaseError::Connection(e));
+                    .map_err(burncloud_database_core::DatabaseError::Connection);
                 tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                 result
             });
@@ -154,7 +212,7 @@ async fn test_connection_pool_exhaustion() {
         let _ = db.close().await;
 
         // Clean up
-        if let Ok(default_path) = get_test_default_path() {
+        if let Ok(default_path) = get_default_database_path() {
             let _ = fs::remove_file(&default_path);
             if let Some(parent) = default_path.parent() {
                 let _ = fs::remove_dir_all(parent);
@@ -168,6 +226,7 @@ async fn test_connection_pool_exhaustion() {
 #[tokio::test]
 async fn test_database_close_scenarios() {
     // Test various database closing scenarios
+    set_test_mode(true);
 
     // Test closing uninitialized database
     let uninitialized_db = Database::new("test_close.db");
@@ -181,7 +240,7 @@ async fn test_database_close_scenarios() {
         assert!(close_result.is_ok(), "Closing initialized database should succeed");
 
         // Clean up
-        if let Ok(default_path) = get_test_default_path() {
+        if let Ok(default_path) = get_default_database_path() {
             let _ = fs::remove_file(&default_path);
             if let Some(parent) = default_path.parent() {
                 let _ = fs::remove_dir_all(parent);
@@ -203,39 +262,40 @@ async fn test_database_close_scenarios() {
 
 #[tokio::test]
 async fn test_malformed_database_paths() {
-    // Test handling of various malformed or problematic paths
-
-    let problematic_paths = vec![
-        "", // Empty path
-        "   ", // Whitespace only
-        "\0", // Null character
-        "/", // Root directory (on Unix)
-        "\\", // Backslash only (on Windows)
-        "?invalid?", // Invalid characters
-        "very/long/path/that/goes/on/and/on/and/should/probably/not/be/a/valid/database/path/in/most/cases", // Very long path
+    // Unusual names are resolved under a temp directory so the suite never
+    // writes into the working directory
+    let temp_dir = tempfile::TempDir::new().expect("Should create temp directory");
+
+    // Awkward but legal file names: initialize creates the file and its parents
+    let creatable = vec![
+        "   ",       // Whitespace only
+        "?invalid?", // Characters that need escaping in URLs
+        "very/long/path/that/goes/on/and/on/and/should/probably/not/be/a/valid/database/path/in/most/cases",
     ];
-
-    for problematic_path in problematic_paths {
-        let mut db = Database::new(problematic_path);
+    for name in creatable {
+        let path = temp_dir.path().join(name);
+        let mut db = Database::new(&path);
         let init_result = db.initialize().await;
+        assert!(init_result.is_ok(), "Path '{}' should be created: {:?}", name, init_result.err());
+        assert!(path.is_file(), "Database file for '{}' should exist", name);
+        let _ = db.close().await;
+    }
 
-        // These should either succeed (if the path is actually valid) or fail gracefully
-        match init_result {
-            Ok(_) => {
-                println!("✓ Unexpected success with path: '{}'", problematic_path);
-                let _ = db.close().await;
-            }
-            Err(e) => {
-                println!("✓ Correctly failed with path '{}': {}", problematic_path, e);
-                // Should be a connection error or similar, not a panic
-            }
-        }
+    // Paths that cannot be a database file fail without panicking
+    let unusable = vec![
+        temp_dir.path().to_path_buf(),        // An existing directory
+        temp_dir.path().join("nul\0byte.db"), // Null character
+    ];
+    for path in unusable {
+        let mut db = Database::new(&path);
+        assert!(db.initialize().await.is_err(), "Path {:?} should fail to open", path);
     }
 }
 
 #[tokio::test]
 async fn test_race_conditions_in_initialization() {
     // Test for race conditions in database initialization
+    set_test_mode(true);
     let num_concurrent = 10;
     let mut handles = vec![];
 
@@ -294,7 +354,7 @@ async fn test_race_conditions_in_initialization() {
         let _ = db.close().await;
     }
 
-    if let Ok(default_path) = get_test_default_path() {
+    if let Ok(default_path) = get_default_database_path() {
         let _ = fs::remove_file(&default_path);
         if let Some(parent) = default_path.parent() {
             let _ = fs::remove_dir_all(parent);
@@ -321,7 +381,7 @@ fn test_error_message_quality() {
     assert!(error_msg.len() > 20);
 
     // Test that errors implement standard traits
-    assert!(format!("{:?}", path_error).len() > 0); // Debug formatting
+    assert!(!format!("{:?}", path_error).is_empty()); // Debug formatting
 
     println!("✓ Error messages are informative and well-formatted");
 }
@@ -330,8 +390,11 @@ fn test_error_message_quality() {
 async fn test_resource_cleanup_on_errors() {
     // Test that resources are properly cleaned up when errors occur
 
-    // Test cleanup when initialization fails
-    let mut db = Database::new("/definitely/invalid/path/that/should/not/exist/database.db");
+    // Test cleanup when initialization fails: the parent "directory" is a regular file
+    let temp_dir = tempfile::TempDir::new().expect("Should create temp directory");
+    let blocker = temp_dir.path().join("not_a_directory");
+    std::fs::write(&blocker, b"").expect("Should create file");
+    let mut db = Database::new(blocker.join("database.db"));
     let init_result = db.initialize().await;
 
     match init_result {
@@ -362,22 +425,120 @@ async fn test_resource_cleanup_on_errors() {
     }
 }
 
-// Helper function for tests
-fn get_test_default_path() -> Result<PathBuf> {
-    use burncloud_database_core::DatabaseError;
-
-    let db_dir = if cfg!(target_os = "windows") {
-        let user_profile = std::env::var("USERPROFILE")
-            .map_err(|e| DatabaseError::PathResolution(format!("USERPROFILE not found: {}", e)))?;
-        PathBuf::from(user_profile)
-            .join("AppData")
-            .join("Local")
-            .join("BurnCloud")
-    } else {
-        dirs::home_dir()
-            .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
-            .join(".burncloud")
-    };
+#[derive(Clone, Default)]
+struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_failed_queries_are_logged_with_parameters() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let mut db = Database::new_in_memory().log_failed_queries(true);
+    db.initialize().await.expect("Should initialize database");
+    db.execute_query("CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL UNIQUE)")
+        .await
+        .unwrap();
+
+    let insert = "INSERT INTO users (email) VALUES (?)";
+    db.execute_query_with_params(insert, vec!["dup@example.com".to_string()]).await.unwrap();
+    assert!(logs.0.lock().unwrap().is_empty(), "Successful queries should not be logged");
+
+    let result = db.execute_query_with_params(insert, vec!["dup@example.com".to_string()]).await;
+    assert!(result.is_err(), "The duplicate insert should violate the UNIQUE constraint");
+
+    let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    assert!(output.contains(insert), "The log should contain the statement: {}", output);
+    assert!(output.contains("dup@example.com"), "The log should contain the value: {}", output);
+    assert!(output.contains("UNIQUE"), "The log should contain the error: {}", output);
+
+    db.close().await.unwrap();
+}
+
+#[test]
+fn test_database_result_alias_interoperates_with_result() {
+    fn check(valid: bool) -> Result<()> {
+        if valid {
+            Ok(())
+        } else {
+            Err(DatabaseError::NotFound)
+        }
+    }
+    fn chained(valid: bool) -> DatabaseResult<()> {
+        check(valid)?;
+        Ok(())
+    }
+    fn outer(valid: bool) -> Result<()> {
+        chained(valid)?;
+        Ok(())
+    }
+
+    assert!(outer(true).is_ok());
+    assert!(matches!(outer(false), Err(DatabaseError::NotFound)));
+}
 
-    Ok(db_dir.join("data.db"))
-}
\ No newline at end of file
+#[tokio::test]
+async fn test_statement_timeout_interrupts_runaway_query() {
+    let mut db = Database::with_pool_size(":memory:", 1);
+    db.initialize().await.expect("Should initialize");
+
+    // Counts forever unless interrupted
+    let runaway = "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n) SELECT MAX(x) FROM n";
+    let started = std::time::Instant::now();
+    let result: Result<Vec<(i64,)>> = db.fetch_all_timeout(runaway, std::time::Duration::from_millis(50)).await;
+    assert!(matches!(result, Err(DatabaseError::Timeout)));
+    assert!(started.elapsed() < std::time::Duration::from_secs(5), "The query should stop promptly");
+
+    // The only pooled connection is usable again
+    let (one,): (i64,) = db
+        .fetch_one_timeout("SELECT 1", std::time::Duration::from_secs(5))
+        .await
+        .expect("Fast query should finish in time");
+    assert_eq!(one, 1);
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_interrupt_handle_cancels_running_query() {
+    let mut db = Database::with_pool_size(":memory:", 1);
+    db.initialize().await.expect("Should initialize");
+    let db = std::sync::Arc::new(db);
+    let handle = db.interrupt_handle().expect("Should create interrupt handle");
+
+    let runner = db.clone();
+    let started = std::time::Instant::now();
+    let query = tokio::spawn(async move {
+        let runaway = "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n) SELECT MAX(x) FROM n";
+        runner.fetch_one::<(i64,)>(runaway).await
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    handle.interrupt();
+
+    let result = query.await.expect("Query task should not panic");
+    assert!(matches!(result, Err(DatabaseError::Connection(_))), "Got {:?}", result);
+    assert!(started.elapsed() < std::time::Duration::from_secs(5), "The query should stop promptly");
+
+    // The connection runs long statements again once it is back in the pool
+    let finite = "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x+1 FROM n WHERE x < 50000) SELECT COUNT(*) FROM n";
+    let (count,): (i64,) = db
+        .fetch_one(finite)
+        .await
+        .expect("Later queries should not be interrupted");
+    assert_eq!(count, 50000);
+}
@@ -0,0 +1,116 @@
+use burncloud_database_core::{create_in_memory_database, Database, DatabaseError, SqlValue};
+use futures_util::StreamExt;
+use serde_json::json;
+use tempfile::TempDir;
+
+// Export tests
+// These tests cover writing database contents out to portable formats
+
+async fn create_two_table_database() -> Database {
+    let db = create_in_memory_database().await.expect("Should create database");
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL, size REAL)")
+        .await
+        .unwrap();
+    db.execute_query("CREATE TABLE tags (model_id INTEGER, tag TEXT, payload BLOB)")
+        .await
+        .unwrap();
+    db.execute_query(
+        "INSERT INTO models (name, size) VALUES ('llama', 7.5), ('mistral, instruct', NULL), ('say \"hi\"', 1.0)",
+    )
+    .await
+    .unwrap();
+    db.execute_query("INSERT INTO tags VALUES (1, 'chat', X'CAFE'), (2, 'code', NULL)")
+        .await
+        .unwrap();
+    db
+}
+
+#[tokio::test]
+async fn test_export_all_csv_writes_one_file_per_table() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let export_dir = temp_dir.path().join("exports").join("nested");
+    let db = create_two_table_database().await;
+    // Crate-managed tables are not exported
+    db.set_meta("build_hash", "abc123").await.unwrap();
+
+    let written = db.export_all_csv(&export_dir).await.expect("Export should succeed");
+    assert_eq!(written, vec![export_dir.join("models.csv"), export_dir.join("tags.csv")]);
+
+    let models = std::fs::read_to_string(export_dir.join("models.csv")).unwrap();
+    let model_lines: Vec<&str> = models.lines().collect();
+    assert_eq!(model_lines.len(), 4, "Header plus three rows");
+    assert_eq!(model_lines[0], "id,name,size");
+    assert_eq!(model_lines[1], "1,llama,7.5");
+    assert_eq!(model_lines[2], "2,\"mistral, instruct\",");
+    assert_eq!(model_lines[3], "3,\"say \"\"hi\"\"\",1");
+
+    let tags = std::fs::read_to_string(export_dir.join("tags.csv")).unwrap();
+    let tag_lines: Vec<&str> = tags.lines().collect();
+    assert_eq!(tag_lines, vec!["model_id,tag,payload", "1,chat,cafe", "2,code,"]);
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_export_csv_empty_table_writes_header() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_query("CREATE TABLE empty_table (a INTEGER, b TEXT)").await.unwrap();
+
+    let path = temp_dir.path().join("empty.csv");
+    let count = db.export_csv("empty_table", &path).await.expect("Export should succeed");
+    assert_eq!(count, 0);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a,b\n");
+
+    assert!(db.export_csv("missing; DROP TABLE x", &path).await.is_err());
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_export_all_csv_quotes_table_names_from_the_schema() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_query("CREATE TABLE \"my table\" (\"first name\" TEXT)").await.unwrap();
+    db.execute_query("INSERT INTO \"my table\" VALUES ('ada')").await.unwrap();
+
+    // The strict default policy would reject "my table" if it came from a caller
+    let written = db.export_all_csv(temp_dir.path()).await.expect("Export should succeed");
+    assert_eq!(written, vec![temp_dir.path().join("my table.csv")]);
+
+    let contents = std::fs::read_to_string(&written[0]).unwrap();
+    assert_eq!(contents.lines().collect::<Vec<_>>(), vec!["first name", "ada"]);
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_fetch_ndjson_stream_yields_one_line_per_row() {
+    let db = create_two_table_database().await;
+
+    let query = "SELECT m.id, m.name, m.size, t.payload
+                 FROM models m LEFT JOIN tags t ON t.model_id = m.id
+                 WHERE m.id >= ? ORDER BY m.id";
+    let lines: Vec<String> = db
+        .fetch_ndjson_stream(query, &[SqlValue::from(1)])
+        .map(|line| line.expect("Row should serialize"))
+        .collect()
+        .await;
+
+    assert_eq!(lines.len(), 3);
+    assert!(lines.iter().all(|line| line.ends_with('\n') && !line.trim_end().contains('\n')));
+
+    let rows: Vec<serde_json::Value> = lines
+        .iter()
+        .map(|line| serde_json::from_str(line).expect("Each line should be valid JSON"))
+        .collect();
+    assert_eq!(rows[0], json!({"id": 1, "name": "llama", "size": 7.5, "payload": "yv4="}));
+    assert_eq!(rows[1], json!({"id": 2, "name": "mistral, instruct", "size": null, "payload": null}));
+    assert_eq!(rows[2]["name"], "say \"hi\"");
+
+    let uninitialized = Database::new_in_memory();
+    let results: Vec<_> = uninitialized.fetch_ndjson_stream("SELECT 1", &[]).collect().await;
+    assert!(matches!(results.as_slice(), [Err(DatabaseError::NotInitialized)]));
+
+    let _ = db.close().await;
+}
@@ -0,0 +1,34 @@
+use burncloud_database_core::{get_default_database_path, set_test_mode, Database};
+
+// Fallback tests
+// These enable the process-wide test mode, so they run in their own test binary
+
+#[tokio::test]
+async fn test_new_default_or_memory_falls_back_when_disk_fails() {
+    set_test_mode(true);
+    let default_path = get_default_database_path().expect("Should resolve default path");
+    let default_dir = default_path.parent().unwrap().to_path_buf();
+
+    // A file where the directory should be makes the default location unusable
+    let _ = std::fs::remove_dir_all(&default_dir);
+    std::fs::write(&default_dir, b"not a directory").expect("Should create blocking file");
+
+    let (db, fell_back) = Database::new_default_or_memory()
+        .await
+        .expect("Should fall back instead of failing");
+    assert!(fell_back, "The fallback should be reported");
+    db.execute_query("CREATE TABLE t (id INTEGER)").await.unwrap();
+    db.close().await.unwrap();
+    std::fs::remove_file(&default_dir).unwrap();
+
+    // With a usable location the file database is opened
+    std::fs::create_dir_all(&default_dir).unwrap();
+    std::fs::File::create(&default_path).unwrap();
+    let (db, fell_back) = Database::new_default_or_memory().await.unwrap();
+    assert!(!fell_back);
+    db.execute_query("CREATE TABLE t (id INTEGER)").await.unwrap();
+    db.close().await.unwrap();
+    assert!(std::fs::metadata(&default_path).unwrap().len() > 0, "Data should be written to disk");
+
+    let _ = std::fs::remove_dir_all(&default_dir);
+}
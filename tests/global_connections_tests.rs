@@ -0,0 +1,36 @@
+use burncloud_database_core::{create_database, create_in_memory_database, Database};
+use tempfile::TempDir;
+
+// Global connection count tests
+// The count is process-wide, so these run in their own test binary
+
+#[tokio::test]
+async fn test_global_open_connections_returns_to_zero() {
+    assert_eq!(Database::global_open_connections(), 0);
+
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let mut databases = Vec::new();
+    for i in 0..3 {
+        let path = temp_dir.path().join(format!("db{}.db", i));
+        std::fs::File::create(&path).expect("Should create database file");
+        databases.push(create_database(&path).await.expect("Should create database"));
+    }
+    databases.push(create_in_memory_database().await.expect("Should create database"));
+
+    // Hold extra connections on one pool to make sure they are all counted
+    let first = databases[0].connection().unwrap();
+    let held = vec![first.acquire().await.unwrap(), first.acquire().await.unwrap()];
+    assert!(Database::global_open_connections() > databases.len());
+    drop(held);
+
+    for db in databases {
+        db.close().await.expect("Should close database");
+    }
+    assert_eq!(Database::global_open_connections(), 0);
+
+    // Dropping without closing also releases the pool
+    let dropped = create_in_memory_database().await.unwrap();
+    assert!(Database::global_open_connections() > 0);
+    drop(dropped);
+    assert_eq!(Database::global_open_connections(), 0);
+}
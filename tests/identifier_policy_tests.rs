@@ -0,0 +1,47 @@
+use burncloud_database_core::{
+    create_in_memory_database, identifier_policy, quote_identifier, set_identifier_policy, DatabaseError,
+    IdentifierPolicy,
+};
+
+// Identifier policy tests
+// The policy is process-wide, so these run in their own test binary
+
+#[tokio::test]
+async fn test_permissive_policy_quotes_unusual_names() {
+    assert_eq!(identifier_policy(), IdentifierPolicy::Strict);
+    assert!(quote_identifier("odd name").is_err());
+
+    set_identifier_policy(IdentifierPolicy::Permissive);
+
+    assert_eq!(quote_identifier("odd name").unwrap(), "\"odd name\"");
+    assert_eq!(quote_identifier("say \"hi\"").unwrap(), "\"say \"\"hi\"\"\"");
+    assert!(matches!(quote_identifier(""), Err(DatabaseError::InvalidData { .. })));
+
+    let db = create_in_memory_database().await.expect("Should create database");
+    db.execute_query("CREATE TABLE \"odd name\" (id INTEGER PRIMARY KEY, \"x; DROP TABLE y\" INTEGER)")
+        .await
+        .unwrap();
+    db.execute_query("CREATE TABLE y (id INTEGER)").await.unwrap();
+    db.execute_query("INSERT INTO \"odd name\" (\"x; DROP TABLE y\") VALUES (1), (2)")
+        .await
+        .unwrap();
+
+    // The malicious-looking name is treated as a column, not as SQL
+    let ids = db
+        .update_returning_ids("odd name", "id = id", "1", &[], "x; DROP TABLE y")
+        .await
+        .expect("Quoted names should be usable");
+    assert_eq!(ids.len(), 2);
+
+    let deleted = db.delete_in_batches("odd name", "1", &[], 1).await.unwrap();
+    assert_eq!(deleted, 2);
+
+    let (tables,): (i64,) = db
+        .fetch_one("SELECT COUNT(*) FROM sqlite_master WHERE name = 'y'")
+        .await
+        .unwrap();
+    assert_eq!(tables, 1);
+
+    set_identifier_policy(IdentifierPolicy::Strict);
+    let _ = db.close().await;
+}
@@ -0,0 +1,69 @@
+use burncloud_database_core::{create_database, Database};
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+
+// Advisory lock tests
+// Two handles on one file stand in for two processes sharing a database
+
+async fn open(path: &Path) -> Database {
+    if !path.exists() {
+        std::fs::File::create(path).expect("Should create database file");
+    }
+    create_database(path).await.expect("Should open database")
+}
+
+#[tokio::test]
+async fn test_lock_is_exclusive_until_released() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let path = temp_dir.path().join("data.db");
+    let first = open(&path).await;
+    let second = open(&path).await;
+
+    let guard = first.try_acquire_lock("migrations").await.unwrap().expect("First acquirer should get the lock");
+    assert!(second.try_acquire_lock("migrations").await.unwrap().is_none(), "The lock is held");
+    assert!(second.try_acquire_lock("backups").await.unwrap().is_some(), "Other names are independent");
+
+    guard.release().await.unwrap();
+    let guard = second.try_acquire_lock("migrations").await.unwrap();
+    assert!(guard.is_some(), "The lock should be free after release");
+
+    // Dropping the guard releases the lock in the background
+    drop(guard);
+    let mut reacquired = None;
+    for _ in 0..50 {
+        reacquired = first.try_acquire_lock("migrations").await.unwrap();
+        if reacquired.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(reacquired.is_some(), "The dropped guard should release the lock");
+
+    drop(reacquired);
+    first.close().await.unwrap();
+    second.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stale_lock_is_reclaimed() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = open(&temp_dir.path().join("data.db")).await;
+
+    let abandoned = db.try_acquire_lock("cleanup").await.unwrap().unwrap();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let reclaimed = db
+        .try_acquire_lock_with_ttl("cleanup", Duration::from_millis(10))
+        .await
+        .unwrap()
+        .expect("A lock older than the TTL should be reclaimed");
+    assert_ne!(reclaimed.owner(), abandoned.owner());
+
+    // The previous holder releasing late must not free the new holder's lock
+    abandoned.release().await.unwrap();
+    assert!(db.try_acquire_lock("cleanup").await.unwrap().is_none());
+
+    reclaimed.release().await.unwrap();
+    db.close().await.unwrap();
+}
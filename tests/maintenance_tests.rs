@@ -0,0 +1,446 @@
+use burncloud_database_core::{
+    create_database, create_in_memory_database, sqlx, CheckpointMode, Database, DatabaseError, FkViolation,
+    JournalMode,
+};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+
+// Maintenance tests
+// These tests cover recovery, backup and housekeeping operations on file databases
+
+async fn create_file_database(path: &Path) -> Database {
+    std::fs::File::create(path).expect("Should create database file");
+    create_database(path).await.expect("Should create database")
+}
+
+async fn create_backup(path: &Path, names: &[&str]) {
+    let db = create_file_database(path).await;
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+        .await
+        .expect("Should create table");
+    for name in names {
+        db.execute_query(&format!("INSERT INTO models (name) VALUES ('{}')", name))
+            .await
+            .expect("Should insert row");
+    }
+    db.close().await.expect("Should close backup");
+}
+
+async fn model_names(db: &Database) -> Vec<String> {
+    let rows: Vec<(String,)> = db
+        .fetch_all("SELECT name FROM models ORDER BY id")
+        .await
+        .expect("Should read models");
+    rows.into_iter().map(|(name,)| name).collect()
+}
+
+#[tokio::test]
+async fn test_open_with_fallback_uses_newest_valid_backup() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let primary = temp_dir.path().join("data.db");
+    let older_backup = temp_dir.path().join("backup-1.db");
+    let newer_backup = temp_dir.path().join("backup-2.db");
+    let corrupt_backup = temp_dir.path().join("backup-3.db");
+
+    create_backup(&older_backup, &["old"]).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    create_backup(&newer_backup, &["llama", "mistral"]).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    std::fs::write(&corrupt_backup, b"definitely not a sqlite database").unwrap();
+
+    std::fs::write(&primary, b"garbage garbage garbage garbage").unwrap();
+
+    let backups: Vec<PathBuf> = vec![older_backup, corrupt_backup, newer_backup];
+    let db = Database::open_with_fallback(&primary, &backups)
+        .await
+        .expect("Should fall back to a backup");
+
+    assert_eq!(model_names(&db).await, vec!["llama", "mistral"]);
+    db.close().await.unwrap();
+
+    // The restored primary is now a standalone valid database
+    let reopened = create_database(&primary).await.expect("Primary should open");
+    assert_eq!(model_names(&reopened).await, vec!["llama", "mistral"]);
+    reopened.close().await.unwrap();
+
+    assert!(temp_dir.path().join("data.db-corrupt").exists(), "Corrupt file should be kept aside");
+}
+
+#[tokio::test]
+async fn test_open_with_fallback_restores_missing_primary() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let primary = temp_dir.path().join("missing-dir").join("data.db");
+    let backup = temp_dir.path().join("backup.db");
+    create_backup(&backup, &["backup"]).await;
+
+    let db = Database::open_with_fallback(&primary, &[backup])
+        .await
+        .expect("Should fall back to the backup");
+    assert_eq!(model_names(&db).await, vec!["backup"]);
+    db.close().await.unwrap();
+    assert!(primary.is_file(), "The backup should be restored to the primary path");
+}
+
+#[tokio::test]
+async fn test_open_with_fallback_prefers_healthy_primary() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let primary = temp_dir.path().join("data.db");
+    let backup = temp_dir.path().join("backup.db");
+
+    create_backup(&primary, &["primary"]).await;
+    create_backup(&backup, &["backup"]).await;
+
+    let db = Database::open_with_fallback(&primary, &[backup])
+        .await
+        .expect("Should open primary");
+    assert_eq!(model_names(&db).await, vec!["primary"]);
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_open_with_fallback_without_valid_backup_fails() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let primary = temp_dir.path().join("data.db");
+    std::fs::write(&primary, b"garbage garbage garbage garbage").unwrap();
+
+    let result = Database::open_with_fallback(&primary, &[temp_dir.path().join("missing.db")]).await;
+    assert!(result.is_err(), "No usable database should be an error");
+    assert!(primary.exists(), "Primary should be left untouched");
+}
+
+#[tokio::test]
+async fn test_open_with_fallback_keeps_earlier_corrupt_copies() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let primary = temp_dir.path().join("data.db");
+    let backup = temp_dir.path().join("backup.db");
+    create_backup(&backup, &["backup"]).await;
+
+    std::fs::write(temp_dir.path().join("data.db-corrupt"), b"earlier corrupt copy").unwrap();
+    std::fs::write(&primary, b"garbage garbage garbage garbage").unwrap();
+
+    let db = Database::open_with_fallback(&primary, &[backup])
+        .await
+        .expect("Should fall back to the backup");
+    assert_eq!(model_names(&db).await, vec!["backup"]);
+    db.close().await.unwrap();
+
+    let read = |name: &str| std::fs::read(temp_dir.path().join(name)).unwrap();
+    assert_eq!(read("data.db-corrupt"), b"earlier corrupt copy");
+    assert_eq!(read("data.db-corrupt-1"), b"garbage garbage garbage garbage");
+}
+
+#[tokio::test]
+async fn test_open_with_fallback_only_replaces_a_damaged_primary() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let backup = temp_dir.path().join("backup.db");
+    create_backup(&backup, &["backup"]).await;
+
+    // A directory cannot be opened, but nothing says it is corrupt
+    let primary = temp_dir.path().join("data.db");
+    std::fs::create_dir(&primary).unwrap();
+
+    let result = Database::open_with_fallback(&primary, &[backup]).await;
+    assert!(result.is_err(), "An unopenable primary should be reported, not replaced");
+    assert!(primary.is_dir());
+    assert!(!temp_dir.path().join("data.db-corrupt").exists());
+}
+
+#[tokio::test]
+async fn test_metadata_persists_across_reopen() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db_path = temp_dir.path().join("meta.db");
+
+    let db = create_file_database(&db_path).await;
+    assert_eq!(db.get_meta("build_hash").await.unwrap(), None);
+    assert!(!db.table_exists("_meta").await.unwrap(), "Reading must not create _meta");
+
+    db.set_meta("build_hash", "abc123").await.expect("Should set metadata");
+    db.set_meta("created_by", "installer").await.expect("Should set metadata");
+    db.set_meta("build_hash", "def456").await.expect("Should overwrite metadata");
+    db.close().await.unwrap();
+
+    let reopened = create_database(&db_path).await.expect("Should reopen database");
+    assert_eq!(reopened.get_meta("build_hash").await.unwrap().as_deref(), Some("def456"));
+    assert_eq!(reopened.get_meta("created_by").await.unwrap().as_deref(), Some("installer"));
+    assert_eq!(reopened.get_meta("missing").await.unwrap(), None);
+    reopened.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_copy_file_to_produces_standalone_copy() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let source_path = temp_dir.path().join("source.db");
+    let copy_path = temp_dir.path().join("snapshots").join("copy.db");
+    std::fs::create_dir_all(copy_path.parent().unwrap()).unwrap();
+
+    let db = create_file_database(&source_path).await;
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+        .await
+        .unwrap();
+    db.execute_query("INSERT INTO models (name) VALUES ('llama'), ('mistral')")
+        .await
+        .unwrap();
+
+    db.copy_file_to(&copy_path).await.expect("Should copy the database");
+
+    // The source keeps working after the copy
+    db.execute_query("INSERT INTO models (name) VALUES ('phi')").await.unwrap();
+    assert_eq!(model_names(&db).await, vec!["llama", "mistral", "phi"]);
+
+    let copy = create_database(&copy_path).await.expect("Copy should open");
+    assert_eq!(model_names(&copy).await, vec!["llama", "mistral"]);
+    copy.close().await.unwrap();
+
+    // Copying again replaces the previous snapshot
+    db.copy_file_to(&copy_path).await.expect("Should overwrite the copy");
+    let copy = create_database(&copy_path).await.expect("Copy should open");
+    assert_eq!(model_names(&copy).await, vec!["llama", "mistral", "phi"]);
+    copy.close().await.unwrap();
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_copy_file_to_refuses_in_memory_database() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_in_memory_database().await.unwrap();
+
+    let result = db.copy_file_to(&temp_dir.path().join("copy.db")).await;
+    assert!(matches!(result, Err(burncloud_database_core::DatabaseError::InvalidData { .. })));
+    assert!(!temp_dir.path().join("copy.db").exists());
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_foreign_key_check_reports_orphans() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_file_database(&temp_dir.path().join("fk.db")).await;
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+        .await
+        .unwrap();
+    db.execute_query("CREATE TABLE tags (model_id INTEGER REFERENCES models(id), tag TEXT)")
+        .await
+        .unwrap();
+    db.execute_query("INSERT INTO models (name) VALUES ('llama')").await.unwrap();
+    db.execute_query("INSERT INTO tags VALUES (1, 'chat')").await.unwrap();
+    assert!(db.foreign_key_check().await.unwrap().is_empty());
+
+    // Foreign keys are a per-connection setting, so bypass them on one connection only
+    {
+        let mut conn = db.connection().unwrap().acquire().await.unwrap();
+        sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await.unwrap();
+        sqlx::query("INSERT INTO tags VALUES (42, 'orphan')")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *conn).await.unwrap();
+    }
+
+    let violations = db.foreign_key_check().await.expect("Check should succeed");
+    assert_eq!(
+        violations,
+        vec![FkViolation {
+            table: "tags".to_string(),
+            rowid: Some(2),
+            parent_table: "models".to_string(),
+            fk_index: 0,
+        }]
+    );
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_analytics_snapshot_is_isolated_from_live_writes() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let primary = temp_dir.path().join("live.db");
+    create_backup(&primary, &["llama", "mistral", "qwen"]).await;
+    let db = create_database(&primary).await.unwrap();
+
+    // The live database accepts writes while the snapshot is being read
+    let live = &db;
+    let (snapshot_count, write_result) = db
+        .with_analytics_snapshot(|snapshot| async move {
+            live.execute_query("INSERT INTO models (name) VALUES ('gemma')").await?;
+            let (count,): (i64,) = snapshot.fetch_one("SELECT COUNT(*) FROM models").await?;
+            let write_result = snapshot.execute_query("INSERT INTO models (name) VALUES ('x')").await;
+            Ok((count, write_result.is_err()))
+        })
+        .await
+        .expect("Snapshot should run");
+    assert_eq!(snapshot_count, 3);
+    assert!(write_result, "The snapshot must be read-only");
+
+    assert_eq!(model_names(&db).await, vec!["llama", "mistral", "qwen", "gemma"]);
+
+    db.execute_query("INSERT INTO models (name) VALUES ('phi')").await.unwrap();
+    let counted = db
+        .with_analytics_snapshot(|snapshot| async move {
+            let (count,): (i64,) = snapshot.fetch_one("SELECT COUNT(*) FROM models").await?;
+            Ok(count)
+        })
+        .await
+        .unwrap();
+    assert_eq!(counted, 5);
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_analytics_snapshot_of_in_memory_database() {
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+        .await
+        .unwrap();
+    db.execute_query("INSERT INTO models (name) VALUES ('llama'), ('qwen')").await.unwrap();
+
+    let counted = db
+        .with_analytics_snapshot(|snapshot| async move {
+            assert!(!snapshot.is_in_memory());
+            let (count,): (i64,) = snapshot.fetch_one("SELECT COUNT(*) FROM models").await?;
+            Ok(count)
+        })
+        .await
+        .expect("Snapshot of an in-memory database should run");
+    assert_eq!(counted, 2);
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_checkpoint_and_report_shrinks_wal() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let path = temp_dir.path().join("data.db");
+    std::fs::File::create(&path).unwrap();
+    let mut db = Database::new_with_journal_mode(&path, JournalMode::Wal);
+    db.initialize().await.expect("Should open WAL database");
+
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+        .await
+        .unwrap();
+    for i in 0..200 {
+        db.execute_query(&format!("INSERT INTO models (name) VALUES ('model-{}')", i))
+            .await
+            .unwrap();
+    }
+
+    let report = db.checkpoint_and_report().await.expect("Should checkpoint");
+    assert!(report.wal_bytes_before > 0, "Writes should have grown the WAL");
+    assert!(report.wal_bytes_after < report.wal_bytes_before);
+    assert!(report.frames_checkpointed > 0);
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_checkpoint_truncate_shrinks_wal_file() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let path = temp_dir.path().join("data.db");
+    let mut db = Database::new_with_journal_mode(&path, JournalMode::Wal);
+    db.initialize().await.expect("Should open WAL database");
+
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+        .await
+        .unwrap();
+    for i in 0..200 {
+        db.execute_query(&format!("INSERT INTO models (name) VALUES ('model-{}')", i))
+            .await
+            .unwrap();
+    }
+
+    let wal_path = temp_dir.path().join("data.db-wal");
+    let wal_before = std::fs::metadata(&wal_path).unwrap().len();
+    let result = db.checkpoint(CheckpointMode::Truncate).await.expect("Should checkpoint");
+    assert!(!result.busy);
+    assert!(wal_before > 0, "Writes should have grown the WAL");
+    assert!(std::fs::metadata(&wal_path).unwrap().len() < wal_before);
+
+    let passive = db.checkpoint(CheckpointMode::Passive).await.unwrap();
+    assert_eq!(passive.checkpointed_frames, passive.log_frames);
+
+    let memory = create_in_memory_database().await.unwrap();
+    assert!(matches!(
+        memory.checkpoint(CheckpointMode::Full).await,
+        Err(DatabaseError::InvalidData { .. })
+    ));
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_integrity_and_quick_check_report_ok_when_healthy() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_file_database(&temp_dir.path().join("data.db")).await;
+    db.execute_batch(
+        "CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+         CREATE INDEX idx_models_name ON models (name);
+         INSERT INTO models (name) VALUES ('gpt-4o'), ('llama');",
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(db.integrity_check().await.unwrap(), vec!["ok".to_string()]);
+    assert_eq!(db.quick_check().await.unwrap(), vec!["ok".to_string()]);
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_vacuum_shrinks_file_after_deletes() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let path = temp_dir.path().join("data.db");
+    let db = create_file_database(&path).await;
+
+    db.execute_query("CREATE TABLE logs (id INTEGER PRIMARY KEY, message TEXT NOT NULL)")
+        .await
+        .unwrap();
+    db.execute_query(
+        "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 5000)
+         INSERT INTO logs (message) SELECT printf('%.200c', 'x') FROM seq",
+    )
+    .await
+    .unwrap();
+    db.execute_query("DELETE FROM logs").await.unwrap();
+    let size_before = std::fs::metadata(&path).unwrap().len();
+
+    db.vacuum().await.expect("Should vacuum");
+    let size_after = std::fs::metadata(&path).unwrap().len();
+    assert!(size_after < size_before, "{} should be smaller than {}", size_after, size_before);
+
+    let copy = temp_dir.path().join("compacted.db");
+    db.vacuum_into(&copy).await.expect("Should write a compacted copy");
+    assert!(copy.exists());
+    let again = db.vacuum_into(&copy).await;
+    assert!(matches!(again, Err(DatabaseError::Query(_))));
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_backup_to_snapshots_file_and_memory_databases() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let file_db = create_file_database(&temp_dir.path().join("data.db")).await;
+    let memory_db = create_in_memory_database().await.unwrap();
+
+    for (db, name) in [(&file_db, "file"), (&memory_db, "memory")] {
+        db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .await
+            .unwrap();
+        db.execute_query("INSERT INTO models (name) VALUES ('llama'), ('qwen')").await.unwrap();
+
+        let backup_path = temp_dir.path().join(format!("{}-backup.db", name));
+        db.backup_to(&backup_path).await.expect("Should back up");
+        // A second backup replaces the first
+        db.execute_query("INSERT INTO models (name) VALUES ('mistral')").await.unwrap();
+        db.backup_to(&backup_path).await.expect("Should overwrite the previous backup");
+
+        let backup = create_database(&backup_path).await.expect("Should open the backup");
+        assert_eq!(model_names(&backup).await, vec!["llama", "qwen", "mistral"]);
+        backup.close().await.unwrap();
+    }
+
+    file_db.close().await.unwrap();
+    memory_db.close().await.unwrap();
+}
@@ -0,0 +1,171 @@
+use burncloud_database_core::{create_in_memory_database, DatabaseError, SqliteMigrationManager};
+use std::path::Path;
+use tempfile::TempDir;
+
+// Migration tests
+// These tests cover applying, re-running and rolling back file-based migrations
+
+fn write_migration(dir: &Path, file_name: &str, sql: &str) {
+    std::fs::write(dir.join(file_name), sql).expect("Should write migration file");
+}
+
+fn write_initial_migrations(dir: &Path) {
+    write_migration(dir, "001_create_models.sql", "CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL);");
+    write_migration(dir, "001_create_models.down.sql", "DROP TABLE models;");
+    write_migration(
+        dir,
+        "002_add_model_size.sql",
+        "ALTER TABLE models ADD COLUMN size INTEGER;\nINSERT INTO models (name, size) VALUES ('base', 7);",
+    );
+}
+
+#[tokio::test]
+async fn test_migrations_apply_in_order_and_rerun_idempotently() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    write_initial_migrations(temp_dir.path());
+    let db = create_in_memory_database().await.expect("Should create database");
+    let manager = SqliteMigrationManager::new(&db, temp_dir.path());
+
+    let applied = manager.run_migrations().await.expect("Should apply migrations");
+    assert_eq!(applied.iter().map(|m| m.version).collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(applied[1].name, "add_model_size");
+
+    let (size,): (i64,) = db.fetch_one("SELECT size FROM models WHERE name = 'base'").await.unwrap();
+    assert_eq!(size, 7);
+
+    let rerun = manager.run_migrations().await.expect("Re-running should succeed");
+    assert!(rerun.is_empty(), "Nothing should be applied twice");
+
+    write_migration(temp_dir.path(), "003_create_tags.sql", "CREATE TABLE tags (name TEXT PRIMARY KEY);");
+    let status = manager.get_migration_status().await.unwrap();
+    assert_eq!(status.len(), 3);
+    assert!(status[..2].iter().all(|m| m.applied_at.is_some()));
+    assert!(status[2].applied_at.is_none(), "The new migration should be pending");
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_edited_migration_is_detected() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    write_initial_migrations(temp_dir.path());
+    let db = create_in_memory_database().await.expect("Should create database");
+    let manager = SqliteMigrationManager::new(&db, temp_dir.path());
+    manager.run_migrations().await.unwrap();
+
+    write_migration(
+        temp_dir.path(),
+        "001_create_models.sql",
+        "CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT);",
+    );
+    let result = manager.run_migrations().await;
+    assert!(matches!(result, Err(DatabaseError::Migration(_))));
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_rollback_reverts_latest_migration() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    write_migration(temp_dir.path(), "001_create_models.sql", "CREATE TABLE models (id INTEGER PRIMARY KEY);");
+    write_migration(temp_dir.path(), "001_create_models.down.sql", "DROP TABLE models;");
+    let db = create_in_memory_database().await.expect("Should create database");
+    let manager = SqliteMigrationManager::new(&db, temp_dir.path());
+    manager.run_migrations().await.unwrap();
+
+    let rolled_back = manager.rollback_migration().await.expect("Should roll back");
+    assert_eq!(rolled_back.map(|m| m.version), Some(1));
+    assert!(db.execute_query("SELECT * FROM models").await.is_err(), "The table should be dropped");
+    assert!(manager.get_migration_status().await.unwrap()[0].applied_at.is_none());
+
+    assert!(manager.rollback_migration().await.unwrap().is_none());
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_history_lists_applied_migrations_in_order() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    write_initial_migrations(temp_dir.path());
+    write_migration(temp_dir.path(), "003_create_tags.sql", "CREATE TABLE tags (name TEXT PRIMARY KEY);");
+    let db = create_in_memory_database().await.expect("Should create database");
+    let manager = SqliteMigrationManager::new(&db, temp_dir.path());
+
+    assert!(manager.history().await.unwrap().is_empty());
+
+    // Apply the first two, then the third on a later run
+    std::fs::rename(temp_dir.path().join("003_create_tags.sql"), temp_dir.path().join("held.txt")).unwrap();
+    manager.run_migrations().await.unwrap();
+    std::fs::rename(temp_dir.path().join("held.txt"), temp_dir.path().join("003_create_tags.sql")).unwrap();
+    manager.run_migrations().await.unwrap();
+
+    let history = manager.history().await.unwrap();
+    assert_eq!(history.iter().map(|m| m.version).collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert!(history.iter().all(|m| m.applied_at.is_some()));
+    assert!(history.windows(2).all(|pair| pair[0].applied_at <= pair[1].applied_at));
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_baseline_migration_reproduces_schema_and_seed_rows() {
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_batch(
+        "CREATE TABLE providers (id INTEGER PRIMARY KEY, name TEXT NOT NULL, weight REAL, logo BLOB);
+         CREATE TABLE request_logs (id INTEGER PRIMARY KEY AUTOINCREMENT, provider_id INTEGER REFERENCES providers(id));
+         CREATE INDEX idx_logs_provider ON request_logs (provider_id);
+         CREATE VIEW provider_names AS SELECT name FROM providers;
+         CREATE TRIGGER providers_audit AFTER INSERT ON providers BEGIN
+             INSERT INTO request_logs (provider_id) VALUES (NEW.id);
+         END;
+         INSERT INTO providers (name, weight, logo) VALUES ('open''ai', 0.5, X'00FF'), ('local', NULL, NULL);",
+    )
+    .await
+    .unwrap();
+
+    let baseline = db.generate_baseline_migration(&["providers"]).await.unwrap();
+    assert!(matches!(
+        db.generate_baseline_migration(&["missing"]).await,
+        Err(DatabaseError::InvalidData { .. })
+    ));
+
+    // Replay through the migration manager into a fresh database
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    write_migration(temp_dir.path(), "001_baseline.sql", &baseline);
+    let fresh = create_in_memory_database().await.unwrap();
+    SqliteMigrationManager::new(&fresh, temp_dir.path()).run_migrations().await.unwrap();
+
+    let schema_query = "SELECT type, name, sql FROM sqlite_master
+        WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%' AND name != '_migrations' ORDER BY name";
+    let original: Vec<(String, String, String)> = db.fetch_all(schema_query).await.unwrap();
+    let replayed: Vec<(String, String, String)> = fresh.fetch_all(schema_query).await.unwrap();
+    assert_eq!(replayed, original);
+
+    let providers_query = "SELECT id, name, weight, logo FROM providers ORDER BY id";
+    type ProviderRow = (i64, String, Option<f64>, Option<Vec<u8>>);
+    let original: Vec<ProviderRow> = db.fetch_all(providers_query).await.unwrap();
+    let replayed: Vec<ProviderRow> = fresh.fetch_all(providers_query).await.unwrap();
+    assert_eq!(replayed, original);
+
+    // Only seed tables carry data, and the trigger did not fire during the replay
+    let (logs,): (i64,) = fresh.fetch_one("SELECT COUNT(*) FROM request_logs").await.unwrap();
+    assert_eq!(logs, 0);
+}
+
+#[tokio::test]
+async fn test_baseline_migration_seeds_tables_whose_names_need_quoting() {
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_batch(
+        "CREATE TABLE \"reference data\" (\"display name\" TEXT);
+         INSERT INTO \"reference data\" VALUES ('gpu');",
+    )
+    .await
+    .unwrap();
+
+    // The strict default policy would reject these names if they came from a caller
+    let baseline = db
+        .generate_baseline_migration(&["reference data"])
+        .await
+        .expect("Existing names should be seeded as stored");
+    assert!(baseline.contains("INSERT INTO \"reference data\" (\"display name\") VALUES ('gpu');"));
+}
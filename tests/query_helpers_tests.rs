@@ -0,0 +1,637 @@
+use burncloud_database_core::{
+    create_in_memory_database, BoolStorage, ColumnValues, Database, DatabaseError, DateTimeStorage, OrderDirection,
+    Page, QueryOptions, SelectBuilder, SqlValue,
+};
+use chrono::{Duration, TimeZone, Utc};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+
+// Query helper tests
+// These tests cover the convenience helpers layered over raw SQL execution
+
+async fn create_events_database(rows: i64) -> Database {
+    let db = create_in_memory_database().await.expect("Should create database");
+    db.execute_query("CREATE TABLE events (id INTEGER PRIMARY KEY, kind TEXT NOT NULL)")
+        .await
+        .expect("Should create table");
+    db.execute_query(&format!(
+        "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < {})
+         INSERT INTO events (kind) SELECT CASE WHEN n % 2 = 0 THEN 'debug' ELSE 'info' END FROM seq",
+        rows
+    ))
+    .await
+    .expect("Should insert rows");
+    db
+}
+
+async fn count_where(db: &Database, kind: &str) -> i64 {
+    let (count,): (i64,) = db
+        .fetch_one(&format!("SELECT COUNT(*) FROM events WHERE kind = '{}'", kind))
+        .await
+        .expect("Should count rows");
+    count
+}
+
+#[tokio::test]
+async fn test_delete_in_batches_removes_all_matching_rows() {
+    let db = create_events_database(1000).await;
+
+    let deleted = db
+        .delete_in_batches("events", "kind = ?", &[SqlValue::from("debug")], 37)
+        .await
+        .expect("Batched delete should succeed");
+
+    assert_eq!(deleted, 500);
+    assert_eq!(count_where(&db, "debug").await, 0);
+    assert_eq!(count_where(&db, "info").await, 500, "Non-matching rows must remain");
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_delete_in_batches_exact_multiple_and_no_matches() {
+    let db = create_events_database(100).await;
+
+    let deleted = db
+        .delete_in_batches("events", "kind = ?", &[SqlValue::from("info")], 10)
+        .await
+        .expect("Batched delete should succeed");
+    assert_eq!(deleted, 50);
+
+    let deleted_again = db
+        .delete_in_batches("events", "kind = ?", &[SqlValue::from("info")], 10)
+        .await
+        .expect("Deleting nothing should succeed");
+    assert_eq!(deleted_again, 0);
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_delete_in_batches_rejects_invalid_arguments() {
+    let db = create_events_database(10).await;
+
+    let zero_batch = db.delete_in_batches("events", "1", &[], 0).await;
+    assert!(matches!(zero_batch, Err(DatabaseError::InvalidData { .. })));
+
+    let bad_table = db.delete_in_batches("events; DROP TABLE events", "1", &[], 10).await;
+    assert!(matches!(bad_table, Err(DatabaseError::InvalidData { .. })));
+    assert_eq!(count_where(&db, "info").await, 5);
+
+    let uninitialized = Database::new_in_memory()
+        .delete_in_batches("events", "1", &[], 10)
+        .await;
+    assert!(matches!(uninitialized, Err(DatabaseError::NotInitialized)));
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_update_returning_ids_reports_changed_rows() {
+    let db = create_events_database(10).await;
+    db.execute_query("ALTER TABLE events ADD COLUMN archived INTEGER NOT NULL DEFAULT 0")
+        .await
+        .unwrap();
+
+    let params = [SqlValue::from(true), SqlValue::from("debug"), SqlValue::from(4)];
+    let mut ids = db
+        .update_returning_ids("events", "archived = ?", "kind = ? AND id > ?", &params, "id")
+        .await
+        .expect("Update should succeed");
+    ids.sort_unstable();
+    assert_eq!(ids, vec![6, 8, 10]);
+
+    let archived: Vec<(i64,)> = db
+        .fetch_all("SELECT id FROM events WHERE archived = 1 ORDER BY id")
+        .await
+        .unwrap();
+    assert_eq!(archived.into_iter().map(|(id,)| id).collect::<Vec<_>>(), ids);
+
+    let none = db
+        .update_returning_ids("events", "archived = 1", "kind = ?", &[SqlValue::from("trace")], "id")
+        .await
+        .expect("Update matching nothing should succeed");
+    assert!(none.is_empty());
+
+    let bad_column = db
+        .update_returning_ids("events", "archived = 1", "1", &[], "id) --")
+        .await;
+    assert!(matches!(bad_column, Err(DatabaseError::InvalidData { .. })));
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_helpers_reject_malicious_identifiers() {
+    let db = create_events_database(10).await;
+    let temp_dir = tempfile::TempDir::new().expect("Should create temp directory");
+    let malicious = "x; DROP TABLE events";
+
+    let deleted = db.delete_in_batches(malicious, "1", &[], 10).await;
+    assert!(matches!(deleted, Err(DatabaseError::InvalidData { .. })));
+
+    let updated_table = db.update_returning_ids(malicious, "kind = 'x'", "1", &[], "id").await;
+    assert!(matches!(updated_table, Err(DatabaseError::InvalidData { .. })));
+
+    let updated_column = db.update_returning_ids("events", "kind = 'x'", "1", &[], malicious).await;
+    assert!(matches!(updated_column, Err(DatabaseError::InvalidData { .. })));
+
+    let exported = db.export_csv(malicious, &temp_dir.path().join("x.csv")).await;
+    assert!(matches!(exported, Err(DatabaseError::InvalidData { .. })));
+
+    assert_eq!(count_where(&db, "info").await, 5, "The table must survive every attempt");
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_fetch_by_ids_splits_large_id_lists() {
+    let db = create_events_database(3000).await;
+
+    let ids: Vec<SqlValue> = (1..=2500).map(SqlValue::from).collect();
+    let rows: Vec<(i64, String)> = db
+        .fetch_by_ids("events", "id", &ids)
+        .await
+        .expect("Fetching more ids than the variable limit should succeed");
+
+    let mut fetched: Vec<i64> = rows.into_iter().map(|(id, _)| id).collect();
+    fetched.sort_unstable();
+    assert_eq!(fetched, (1..=2500).collect::<Vec<_>>());
+
+    let none: Vec<(i64, String)> = db.fetch_by_ids("events", "id", &[]).await.unwrap();
+    assert!(none.is_empty());
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_fetch_map_by_ids_keys_rows_by_id() {
+    let db = create_events_database(10).await;
+
+    #[derive(sqlx::FromRow, Debug, PartialEq)]
+    struct Event {
+        id: i64,
+        kind: String,
+    }
+
+    let ids: Vec<SqlValue> = [2, 5, 7, 42].into_iter().map(SqlValue::from).collect();
+    let events: HashMap<i64, Event> = db.fetch_map_by_ids("events", "id", &ids).await.unwrap();
+
+    assert_eq!(events.len(), 3, "Missing ids should be absent from the map");
+    for id in [2, 5, 7] {
+        assert_eq!(events[&id].id, id);
+    }
+    assert_eq!(events[&2].kind, "debug");
+    assert_eq!(events[&5].kind, "info");
+    assert!(!events.contains_key(&42));
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_fetch_columnar_lines_up_with_rows() {
+    let db = create_in_memory_database().await.expect("Should create database");
+    db.execute_query("CREATE TABLE readings (sensor TEXT, value REAL)").await.unwrap();
+    db.execute_query("INSERT INTO readings VALUES ('a', 1.5), ('b', NULL), (NULL, 3)").await.unwrap();
+
+    let query = "SELECT sensor, value FROM readings WHERE value IS NULL OR value > ? ORDER BY rowid";
+    let batch = db.fetch_columnar(query, &[SqlValue::from(0)]).await.unwrap();
+
+    assert_eq!(batch.num_rows, 3);
+    assert_eq!(
+        batch.columns.iter().map(|column| column.name.as_str()).collect::<Vec<_>>(),
+        vec!["sensor", "value"]
+    );
+    assert_eq!(
+        batch.column("sensor"),
+        Some(&ColumnValues::Text(vec![Some("a".to_string()), Some("b".to_string()), None]))
+    );
+    assert_eq!(batch.column("value"), Some(&ColumnValues::Real(vec![Some(1.5), None, Some(3.0)])));
+
+    let empty = db
+        .fetch_columnar("SELECT sensor, value FROM readings WHERE value > ?", &[SqlValue::from(100)])
+        .await
+        .unwrap();
+    assert_eq!(empty.num_rows, 0);
+    assert_eq!(empty.columns.len(), 2, "Column names should be known without rows");
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_insert_many_splits_wide_batches() {
+    let db = create_events_database(0).await;
+    db.execute_query("DELETE FROM events").await.unwrap();
+
+    let rows: Vec<Vec<SqlValue>> = (1..=1200)
+        .map(|id| vec![SqlValue::from(id), SqlValue::from(if id % 2 == 0 { "debug" } else { "info" })])
+        .collect();
+    let inserted = db
+        .insert_many("events", &["id", "kind"], &rows)
+        .await
+        .expect("Inserting more values than the variable limit should succeed");
+    assert_eq!(inserted, 1200);
+    assert_eq!(count_where(&db, "debug").await, 600);
+
+    let mismatched = db
+        .insert_many("events", &["id", "kind"], &[vec![SqlValue::from(5000)]])
+        .await;
+    assert!(matches!(mismatched, Err(DatabaseError::InvalidData { .. })));
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_insert_many_returning_ids_in_order() {
+    let db = create_events_database(3).await;
+
+    let rows: Vec<Vec<SqlValue>> = (0..1500).map(|i| vec![SqlValue::from(format!("kind-{}", i))]).collect();
+    let ids = db
+        .insert_many_returning_ids("events", &["kind"], &rows)
+        .await
+        .expect("Should insert and return ids");
+
+    assert_eq!(ids.len(), 1500);
+    assert_eq!(ids, (4..4 + 1500).collect::<Vec<i64>>(), "Ids should be sequential in insertion order");
+
+    let stored: Vec<(i64, String)> = db
+        .fetch_all("SELECT id, kind FROM events WHERE id > 3 ORDER BY id")
+        .await
+        .unwrap();
+    for ((id, kind), (expected_id, row)) in stored.iter().zip(ids.iter().zip(&rows)) {
+        assert_eq!(id, expected_id);
+        assert_eq!(&SqlValue::from(kind.as_str()), &row[0]);
+    }
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_insert_many_returning_ids_follows_row_order() {
+    let db = create_events_database(0).await;
+
+    let rows: Vec<Vec<SqlValue>> = [50, 10, 30]
+        .iter()
+        .map(|&id| vec![SqlValue::Integer(id), SqlValue::from(format!("kind-{}", id))])
+        .collect();
+    let ids = db
+        .insert_many_returning_ids("events", &["id", "kind"], &rows)
+        .await
+        .expect("Should insert and return ids");
+
+    assert_eq!(ids, vec![50, 10, 30], "Ids should follow the order of the rows, not of the rowids");
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_datetime_params_in_both_storage_modes() {
+    let base = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+
+    for storage in [DateTimeStorage::Rfc3339, DateTimeStorage::UnixSeconds] {
+        let mut db = Database::new_in_memory().datetime_storage(storage);
+        db.initialize().await.expect("Should initialize database");
+        db.execute_query("CREATE TABLE runs (id INTEGER PRIMARY KEY, started_at)").await.unwrap();
+
+        let rows: Vec<Vec<SqlValue>> = (0..5)
+            .map(|day| vec![SqlValue::from(day + 1), SqlValue::from(base + Duration::days(day))])
+            .collect();
+        db.insert_many("runs", &["id", "started_at"], &rows).await.unwrap();
+
+        let range = [
+            SqlValue::from(base + Duration::hours(12)),
+            SqlValue::from(base + Duration::days(3)),
+        ];
+        let mut ids = db
+            .update_returning_ids("runs", "id = id", "started_at BETWEEN ? AND ?", &range, "id")
+            .await
+            .expect("Range query should succeed");
+        ids.sort_unstable();
+        assert_eq!(ids, vec![2, 3, 4], "{:?} should compare chronologically", storage);
+
+        let (stored,): (String,) = db
+            .fetch_one("SELECT CAST(started_at AS TEXT) FROM runs WHERE id = 1")
+            .await
+            .unwrap();
+        match storage {
+            DateTimeStorage::Rfc3339 => assert_eq!(stored, "2024-05-01T12:00:00.000000Z"),
+            DateTimeStorage::UnixSeconds => assert_eq!(stored, base.timestamp().to_string()),
+        }
+
+        let _ = db.close().await;
+    }
+}
+
+#[tokio::test]
+async fn test_bool_params_in_both_storage_modes() {
+    for (storage, stored_true) in [
+        (BoolStorage::Integer, SqlValue::Integer(1)),
+        (BoolStorage::Text, SqlValue::Text("true".to_string())),
+    ] {
+        let mut db = Database::new_in_memory().bool_storage(storage);
+        db.initialize().await.expect("Should initialize database");
+        db.execute_query("CREATE TABLE deployments (id INTEGER PRIMARY KEY, gpu_required)")
+            .await
+            .unwrap();
+
+        let rows: Vec<Vec<SqlValue>> = (1..=4)
+            .map(|id| vec![SqlValue::from(id), SqlValue::from(id % 2 == 0)])
+            .collect();
+        db.insert_many("deployments", &["id", "gpu_required"], &rows).await.unwrap();
+
+        let mut ids = db
+            .update_returning_ids("deployments", "id = id", "gpu_required = ?", &[SqlValue::from(true)], "id")
+            .await
+            .expect("Filtering on a bool parameter should succeed");
+        ids.sort_unstable();
+        assert_eq!(ids, vec![2, 4], "{:?} should filter on the stored representation", storage);
+
+        let (kind, text): (String, String) = db
+            .fetch_one("SELECT typeof(gpu_required), CAST(gpu_required AS TEXT) FROM deployments WHERE id = 2")
+            .await
+            .unwrap();
+        let stored = if kind == "integer" {
+            SqlValue::Integer(text.parse().unwrap())
+        } else {
+            SqlValue::Text(text)
+        };
+        assert_eq!(stored, stored_true);
+        assert_eq!(stored.as_bool(), Some(true));
+
+        let _ = db.close().await;
+    }
+
+    assert_eq!(SqlValue::Text("false".to_string()).as_bool(), Some(false));
+    assert_eq!(SqlValue::Integer(0).as_bool(), Some(false));
+    assert_eq!(SqlValue::Integer(7).as_bool(), None);
+}
+
+#[tokio::test]
+async fn test_fetch_stream_yields_rows_incrementally() {
+    let db = create_events_database(10_000).await;
+
+    let mut stream = db.fetch_stream::<(i64, String)>("SELECT id, kind FROM events ORDER BY id");
+    let first = stream.next().await.expect("Stream should yield").expect("Row should decode");
+    assert_eq!(first, (1, "info".to_string()));
+
+    let mut count = 1;
+    let mut last_id = first.0;
+    while let Some(row) = stream.next().await {
+        let (id, _) = row.expect("Row should decode");
+        assert_eq!(id, last_id + 1, "Rows should arrive in order");
+        last_id = id;
+        count += 1;
+    }
+    assert_eq!(count, 10_000);
+    drop(stream);
+
+    // Taking a prefix stops fetching early
+    let prefix: Vec<(i64,)> = db
+        .fetch_stream::<(i64,)>("SELECT id FROM events ORDER BY id")
+        .take(3)
+        .map(|row| row.unwrap())
+        .collect()
+        .await;
+    assert_eq!(prefix, vec![(1,), (2,), (3,)]);
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_execute_batch_runs_every_statement() {
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_batch(
+        "CREATE TABLE providers (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+         INSERT INTO providers (name) VALUES ('openai; azure'), ('anthropic');
+         SELECT name FROM providers;",
+    )
+    .await
+    .unwrap();
+
+    let names: Vec<(String,)> = db.fetch_all("SELECT name FROM providers ORDER BY id").await.unwrap();
+    assert_eq!(names, vec![("openai; azure".to_string(),), ("anthropic".to_string(),)]);
+}
+
+#[tokio::test]
+async fn test_execute_batch_reports_failing_statement() {
+    let db = create_in_memory_database().await.unwrap();
+    let result = db
+        .execute_batch(
+            "CREATE TABLE a (id INTEGER);
+             INSERT INTO missing VALUES (1);
+             CREATE TABLE b (id INTEGER);",
+        )
+        .await;
+
+    match result {
+        Err(DatabaseError::Query(message)) => {
+            assert!(message.contains("Statement 2 of 3"), "{}", message);
+            assert!(message.contains("INSERT INTO missing"), "{}", message);
+        }
+        other => panic!("Expected a Query error, got {:?}", other),
+    }
+    // Statements before the failure stay applied, later ones never run
+    assert!(db.execute_query("SELECT * FROM a").await.is_ok());
+    assert!(db.execute_query("SELECT * FROM b").await.is_err());
+}
+
+#[tokio::test]
+async fn test_execute_batch_rolls_back_transaction_on_failure() {
+    let mut db = Database::with_pool_size(":memory:", 1);
+    db.initialize().await.unwrap();
+
+    let result = db
+        .execute_batch("BEGIN; CREATE TABLE queued (x); INSERT INTO missing VALUES (1); COMMIT;")
+        .await;
+    assert!(matches!(result, Err(DatabaseError::Query(_))));
+
+    // The pooled connection is usable and the half-applied script is gone
+    db.execute_query("CREATE TABLE later (x)").await.expect("Connection should not be left in a transaction");
+    assert!(!db.table_exists("queued").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_insert_returns_rowid() {
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE)")
+        .await
+        .unwrap();
+
+    let insert = "INSERT INTO models (name) VALUES (?)";
+    assert_eq!(db.insert(insert, &[SqlValue::Text("gpt-4o".into())]).await.unwrap(), 1);
+    assert_eq!(db.insert(insert, &[SqlValue::Text("claude".into())]).await.unwrap(), 2);
+
+    // An ignored insert must not report the previous rowid
+    let ignored = db
+        .insert("INSERT OR IGNORE INTO models (name) VALUES (?)", &[SqlValue::Text("gpt-4o".into())])
+        .await;
+    assert!(matches!(ignored, Err(DatabaseError::Query(_))));
+}
+
+#[tokio::test]
+async fn test_execute_count_returns_rows_affected() {
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_query("CREATE TABLE sessions (id INTEGER PRIMARY KEY, expired INTEGER NOT NULL)")
+        .await
+        .unwrap();
+    assert_eq!(db.execute_count("INSERT INTO sessions (expired) VALUES (1), (1), (0)").await.unwrap(), 3);
+
+    assert_eq!(db.execute_count("DELETE FROM sessions WHERE expired = 1").await.unwrap(), 2);
+    let params = [SqlValue::Integer(1), SqlValue::Integer(0)];
+    let updated = db
+        .execute_count_with("UPDATE sessions SET expired = ? WHERE expired = ?", &params)
+        .await
+        .unwrap();
+    assert_eq!(updated, 1);
+}
+
+#[tokio::test]
+async fn test_select_applies_limit_offset_and_order() {
+    let db = create_events_database(10).await;
+
+    let page = QueryOptions {
+        limit: Some(3),
+        offset: Some(3),
+        order_by: Some("id".to_string()),
+        order_direction: OrderDirection::Asc,
+    };
+    let rows: Vec<(i64,)> = db.select("events", &["id"], &page).await.unwrap();
+    assert_eq!(rows, vec![(4,), (5,), (6,)]);
+
+    let descending = QueryOptions {
+        limit: Some(2),
+        order_by: Some("id".to_string()),
+        order_direction: OrderDirection::Desc,
+        ..Default::default()
+    };
+    let rows: Vec<(i64, String)> = db.select("events", &[], &descending).await.unwrap();
+    assert_eq!(rows, vec![(10, "debug".to_string()), (9, "info".to_string())]);
+
+    // An offset on its own skips rows without capping the result
+    let tail = QueryOptions {
+        offset: Some(8),
+        order_by: Some("id".to_string()),
+        ..Default::default()
+    };
+    let rows: Vec<(i64,)> = db.select("events", &["id"], &tail).await.unwrap();
+    assert_eq!(rows, vec![(9,), (10,)]);
+}
+
+#[tokio::test]
+async fn test_select_builder_rejects_unsafe_order_by() {
+    let options = QueryOptions {
+        order_by: Some("id; DROP TABLE events".to_string()),
+        ..Default::default()
+    };
+    let result = SelectBuilder::new("events", &["id"]).options(&options).build();
+    assert!(matches!(result, Err(DatabaseError::InvalidData { .. })));
+
+    let (sql, params) = SelectBuilder::new("events", &["id", "kind"])
+        .options(&QueryOptions {
+            limit: Some(5),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    assert_eq!(sql, "SELECT \"id\", \"kind\" FROM \"events\" LIMIT ?");
+    assert_eq!(params, vec![SqlValue::Integer(5)]);
+}
+
+#[tokio::test]
+async fn test_paginate_returns_page_and_total() {
+    let db = create_events_database(25).await;
+    let options = QueryOptions {
+        limit: Some(10),
+        offset: Some(10),
+        order_by: Some("id".to_string()),
+        ..Default::default()
+    };
+
+    let page: Page<(i64, String)> = db.paginate("SELECT id, kind FROM events", &options).await.unwrap();
+    assert_eq!(page.items.len(), 10);
+    assert_eq!(page.total, 25);
+    assert_eq!((page.limit, page.offset), (Some(10), 10));
+    assert_eq!(page.items.first().map(|row| row.0), Some(11));
+
+    // The base query's own ORDER BY and LIMIT do not shrink the total
+    let filtered: Page<(i64,)> = db
+        .paginate("SELECT id FROM events WHERE kind = 'info' ORDER BY id DESC LIMIT 3", &QueryOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(filtered.items, vec![(25,), (23,), (21,)]);
+    assert_eq!(filtered.total, 13);
+}
+
+#[tokio::test]
+async fn test_upsert_inserts_then_updates_by_key() {
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_query("CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)")
+        .await
+        .unwrap();
+
+    let columns = ["key", "value"];
+    let first = [SqlValue::Text("theme".into()), SqlValue::Text("light".into())];
+    assert_eq!(db.upsert("settings", &["key"], &columns, &first).await.unwrap(), 1);
+    let second = [SqlValue::Text("theme".into()), SqlValue::Text("dark".into())];
+    assert_eq!(db.upsert("settings", &["key"], &columns, &second).await.unwrap(), 1);
+
+    let rows: Vec<(String, String)> = db.fetch_all("SELECT key, value FROM settings").await.unwrap();
+    assert_eq!(rows, vec![("theme".to_string(), "dark".to_string())]);
+
+    let bad_key = db.upsert("settings", &["missing"], &columns, &second).await;
+    assert!(matches!(bad_key, Err(DatabaseError::InvalidData { .. })));
+    let bad_table = db.upsert("settings; DROP TABLE settings", &["key"], &columns, &second).await;
+    assert!(matches!(bad_table, Err(DatabaseError::InvalidData { .. })));
+}
+
+#[tokio::test]
+async fn test_fetch_all_json_maps_sqlite_types() {
+    let db = create_in_memory_database().await.unwrap();
+    let rows = db
+        .fetch_all_json("SELECT 42 AS id, 0.5 AS ratio, 'gpt-4o' AS name, X'00FF' AS logo, NULL AS note")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rows,
+        vec![serde_json::json!({
+            "id": 42,
+            "ratio": 0.5,
+            "name": "gpt-4o",
+            "logo": "AP8=",
+            "note": null,
+        })]
+    );
+    assert!(rows[0]["id"].is_i64());
+    assert!(rows[0]["ratio"].is_f64());
+    assert!(db.fetch_all_json("SELECT 1 WHERE 0").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_run_reports_rows_and_insert_results() {
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+        .await
+        .unwrap();
+
+    let inserted = db
+        .run("INSERT INTO models (name) VALUES (?)", &[SqlValue::Text("gpt-4o".into())])
+        .await
+        .unwrap();
+    assert!(inserted.rows.is_empty());
+    assert_eq!(inserted.rows_affected, 1);
+    assert_eq!(inserted.last_insert_id, Some(1));
+
+    let selected = db
+        .run("SELECT id, name FROM models WHERE id = ?", &[SqlValue::Integer(1)])
+        .await
+        .unwrap();
+    assert_eq!(selected.rows.len(), 1);
+    assert_eq!(selected.rows[0]["name"], serde_json::json!("gpt-4o"));
+    assert_eq!(selected.rows_affected, 0);
+    assert_eq!(selected.last_insert_id, None);
+
+    // An UPDATE does not report the rowid of the earlier insert
+    let updated = db.run("UPDATE models SET name = 'gpt-4.1'", &[]).await.unwrap();
+    assert_eq!((updated.rows_affected, updated.last_insert_id), (1, None));
+}
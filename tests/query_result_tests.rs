@@ -0,0 +1,49 @@
+use burncloud_database_core::{DatabaseError, QueryResult};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+
+// Query result tests
+// These tests cover the dynamically typed QueryResult and its conversions
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Model {
+    id: i64,
+    name: String,
+    size: Option<f64>,
+}
+
+fn row(value: serde_json::Value) -> HashMap<String, serde_json::Value> {
+    serde_json::from_value(value).expect("Should build row")
+}
+
+#[test]
+fn test_deserialize_rows_into_struct() {
+    let result = QueryResult {
+        rows: vec![
+            row(json!({"id": 1, "name": "llama", "size": 7.5})),
+            row(json!({"id": 2, "name": "mistral", "size": null})),
+        ],
+        ..Default::default()
+    };
+
+    let models: Vec<Model> = result.deserialize_rows().expect("Rows should deserialize");
+    assert_eq!(
+        models,
+        vec![
+            Model { id: 1, name: "llama".to_string(), size: Some(7.5) },
+            Model { id: 2, name: "mistral".to_string(), size: None },
+        ]
+    );
+}
+
+#[test]
+fn test_deserialize_rows_reports_type_mismatch() {
+    let result = QueryResult {
+        rows: vec![row(json!({"id": "one", "name": "llama", "size": null}))],
+        ..Default::default()
+    };
+
+    let models = result.deserialize_rows::<Model>();
+    assert!(matches!(models, Err(DatabaseError::InvalidData { .. })));
+}
@@ -0,0 +1,268 @@
+use burncloud_database_core::{
+    create_in_memory_database, ColumnInfo, ColumnSpec, Database, DatabaseError, SchemaDiff, SqlValue, TableSpec,
+};
+
+// Schema tests
+// These tests cover comparing and inspecting database schemas
+
+async fn create_models_database(with_index: bool) -> Database {
+    let db = create_in_memory_database().await.expect("Should create database");
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE, size REAL)")
+        .await
+        .unwrap();
+    if with_index {
+        db.execute_query("CREATE INDEX idx_models_size ON models (size)")
+            .await
+            .unwrap();
+    }
+    db
+}
+
+#[tokio::test]
+async fn test_schema_diff_reports_missing_index() {
+    let reference = create_models_database(true).await;
+    let migrated = create_models_database(false).await;
+
+    let diffs = migrated.schema_diff(&reference).await.expect("Diff should succeed");
+    assert_eq!(
+        diffs,
+        vec![SchemaDiff::MissingIndex {
+            table: "models".to_string(),
+            index: "idx_models_size".to_string(),
+        }]
+    );
+
+    let reverse = reference.schema_diff(&migrated).await.unwrap();
+    assert_eq!(
+        reverse,
+        vec![SchemaDiff::ExtraIndex {
+            table: "models".to_string(),
+            index: "idx_models_size".to_string(),
+        }]
+    );
+
+    let _ = reference.close().await;
+    let _ = migrated.close().await;
+}
+
+#[tokio::test]
+async fn test_schema_diff_tables_and_columns() {
+    let reference = create_models_database(true).await;
+    let other = create_models_database(true).await;
+    assert!(other.schema_diff(&reference).await.unwrap().is_empty());
+
+    other.execute_query("ALTER TABLE models ADD COLUMN family TEXT").await.unwrap();
+    other.execute_query("CREATE TABLE tags (tag TEXT)").await.unwrap();
+    reference.execute_query("CREATE TABLE usage (tokens INTEGER)").await.unwrap();
+
+    let diffs = other.schema_diff(&reference).await.unwrap();
+    assert_eq!(
+        diffs,
+        vec![
+            SchemaDiff::ExtraColumn {
+                table: "models".to_string(),
+                column: "family".to_string(),
+            },
+            SchemaDiff::MissingTable {
+                table: "usage".to_string(),
+            },
+            SchemaDiff::ExtraTable {
+                table: "tags".to_string(),
+            },
+        ]
+    );
+
+    let _ = reference.close().await;
+    let _ = other.close().await;
+}
+
+#[tokio::test]
+async fn test_create_table_emits_check_constraints() {
+    let db = create_in_memory_database().await.expect("Should create database");
+    let spec = TableSpec::new("deployments")
+        .column(ColumnSpec::new("id", "TEXT").primary_key())
+        .column(ColumnSpec::new("port", "INTEGER").not_null().check("port BETWEEN 1 AND 65535"))
+        .column(ColumnSpec::new("temperature", "REAL"))
+        .check("temperature IS NULL OR temperature >= 0");
+
+    db.create_table(&spec).await.expect("Should create table");
+    db.create_table(&spec).await.expect("Creating the table again should be a no-op");
+
+    db.execute_query("INSERT INTO deployments VALUES ('a', 8080, 0.7)")
+        .await
+        .expect("In-range values should be accepted");
+
+    let out_of_range = db.execute_query("INSERT INTO deployments VALUES ('b', 70000, 0.7)").await;
+    assert!(matches!(out_of_range, Err(DatabaseError::ConstraintViolation { .. })));
+    let negative = db.execute_query("INSERT INTO deployments VALUES ('c', 80, -1.0)").await;
+    assert!(matches!(negative, Err(DatabaseError::ConstraintViolation { .. })));
+
+    let (count,): (i64,) = db.fetch_one("SELECT COUNT(*) FROM deployments").await.unwrap();
+    assert_eq!(count, 1);
+
+    let bad_name = db
+        .create_table(&TableSpec::new("t; DROP TABLE deployments").column(ColumnSpec::new("id", "TEXT")))
+        .await;
+    assert!(matches!(bad_name, Err(DatabaseError::InvalidData { .. })));
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_create_table_without_rowid() {
+    let db = create_in_memory_database().await.expect("Should create database");
+    let spec = TableSpec::new("models")
+        .column(ColumnSpec::new("id", "TEXT").primary_key())
+        .column(ColumnSpec::new("name", "TEXT").not_null())
+        .without_rowid();
+    db.create_table(&spec).await.expect("Should create table");
+
+    let (sql,): (String,) = db
+        .fetch_one("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'models'")
+        .await
+        .unwrap();
+    assert!(sql.ends_with("WITHOUT ROWID"), "Unexpected table SQL: {}", sql);
+
+    let id = uuid::Uuid::new_v4().to_string();
+    db.execute_query_with_params("INSERT INTO models (id, name) VALUES (?, ?)", vec![id.clone(), "llama".to_string()])
+        .await
+        .unwrap();
+    let (name,): (String,) = db
+        .fetch_one(&format!("SELECT name FROM models WHERE id = '{}'", id))
+        .await
+        .unwrap();
+    assert_eq!(name, "llama");
+    assert!(db.execute_query("SELECT rowid FROM models").await.is_err(), "There should be no rowid");
+
+    let no_key = TableSpec::new("logs").column(ColumnSpec::new("line", "TEXT")).without_rowid();
+    assert!(matches!(db.create_table(&no_key).await, Err(DatabaseError::InvalidData { .. })));
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_uses_index_detects_full_scans() {
+    let db = create_models_database(true).await;
+
+    let indexed = db
+        .uses_index("SELECT * FROM models WHERE size > ?", &[SqlValue::from(1.0)])
+        .await
+        .expect("Should explain query");
+    assert!(indexed);
+
+    let by_unique_name = db
+        .uses_index("SELECT id FROM models WHERE name = 'llama'", &[])
+        .await
+        .unwrap();
+    assert!(by_unique_name);
+
+    db.execute_query("ALTER TABLE models ADD COLUMN family TEXT").await.unwrap();
+    let unindexed = db
+        .uses_index("SELECT * FROM models WHERE family = ?", &[SqlValue::from("llama")])
+        .await
+        .unwrap();
+    assert!(!unindexed);
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_triggers_lists_installed_triggers() {
+    let db = create_models_database(false).await;
+    db.execute_query("ALTER TABLE models ADD COLUMN updated_at TEXT").await.unwrap();
+    assert!(db.triggers("models").await.unwrap().is_empty());
+
+    db.execute_query(
+        "CREATE TRIGGER models_updated_at AFTER UPDATE ON models
+         BEGIN UPDATE models SET updated_at = datetime('now') WHERE id = NEW.id; END",
+    )
+    .await
+    .unwrap();
+    db.execute_query("CREATE TABLE tags (tag TEXT)").await.unwrap();
+    db.execute_query("CREATE TRIGGER tags_noop AFTER INSERT ON tags BEGIN SELECT 1; END")
+        .await
+        .unwrap();
+
+    let triggers = db.triggers("models").await.expect("Should list triggers");
+    assert_eq!(triggers.len(), 1, "Triggers on other tables should not be listed");
+    assert_eq!(triggers[0].name, "models_updated_at");
+    assert!(triggers[0].sql.starts_with("CREATE TRIGGER models_updated_at AFTER UPDATE ON models"));
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_table_exists() {
+    let db = create_models_database(false).await;
+    db.execute_query("CREATE VIEW model_names AS SELECT name FROM models").await.unwrap();
+
+    assert!(db.table_exists("models").await.unwrap());
+    assert!(!db.table_exists("missing").await.unwrap());
+    assert!(!db.table_exists("model_names").await.unwrap(), "Views are not tables");
+    assert!(!db.table_exists("models'; DROP TABLE models; --").await.unwrap());
+    assert!(db.table_exists("models").await.unwrap());
+
+    let uninitialized = Database::new_in_memory();
+    assert!(matches!(uninitialized.table_exists("models").await, Err(DatabaseError::NotInitialized)));
+}
+
+#[tokio::test]
+async fn test_list_tables() {
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_batch(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT);
+         CREATE TABLE api_keys (id INTEGER PRIMARY KEY);
+         CREATE TABLE models (id INTEGER PRIMARY KEY);
+         CREATE INDEX idx_models ON models (id);
+         CREATE VIEW user_ids AS SELECT id FROM users;
+         INSERT INTO users DEFAULT VALUES;",
+    )
+    .await
+    .unwrap();
+
+    // AUTOINCREMENT creates sqlite_sequence, which is left out
+    assert_eq!(db.list_tables().await.unwrap(), vec!["api_keys", "models", "users"]);
+
+    // So are the tables this crate manages for itself
+    db.set_meta("build_hash", "abc123").await.unwrap();
+    let _lock = db.try_acquire_lock("reindex").await.unwrap();
+    assert!(db.table_exists("_meta").await.unwrap());
+    assert!(db.table_exists("_locks").await.unwrap());
+    assert_eq!(db.list_tables().await.unwrap(), vec!["api_keys", "models", "users"]);
+}
+
+#[tokio::test]
+async fn test_table_columns() {
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_query(
+        "CREATE TABLE usage (tenant TEXT NOT NULL, day TEXT, tokens INTEGER NOT NULL DEFAULT 0, cost REAL, raw BLOB, note,
+                             PRIMARY KEY (tenant, day))",
+    )
+    .await
+    .unwrap();
+
+    let column = |name: &str, data_type: &str, nullable: bool, primary_key: bool| ColumnInfo {
+        name: name.to_string(),
+        data_type: data_type.to_string(),
+        nullable,
+        primary_key,
+    };
+    assert_eq!(
+        db.table_columns("usage").await.unwrap(),
+        vec![
+            column("tenant", "TEXT", false, true),
+            column("day", "TEXT", true, true),
+            column("tokens", "INTEGER", false, false),
+            column("cost", "REAL", true, false),
+            column("raw", "BLOB", true, false),
+            column("note", "", true, false),
+        ]
+    );
+
+    assert!(matches!(db.table_columns("missing").await, Err(DatabaseError::InvalidData { .. })));
+    assert!(matches!(
+        db.table_columns("usage); DROP TABLE usage; --").await,
+        Err(DatabaseError::InvalidData { .. })
+    ));
+    assert!(db.table_exists("usage").await.unwrap());
+}
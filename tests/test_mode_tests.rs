@@ -0,0 +1,58 @@
+use burncloud_database_core::{create_default_database, get_default_database_path, is_test_mode, set_test_mode};
+
+// Test mode tests
+// Test mode is process-wide, so these tests live in their own test binary
+
+#[tokio::test]
+async fn test_test_mode_redirects_default_path_to_temp_dir() {
+    assert!(!is_test_mode(), "Test mode should be off by default");
+    let real_path = get_default_database_path().expect("Should resolve default path");
+    assert!(!real_path.starts_with(std::env::temp_dir()));
+
+    // Without test mode, a missing USERPROFILE is a path resolution error
+    #[cfg(target_os = "windows")]
+    {
+        use burncloud_database_core::{Database, DatabaseError};
+
+        let original_userprofile = std::env::var("USERPROFILE").ok();
+        std::env::remove_var("USERPROFILE");
+
+        let new_default_error = Database::new_default();
+        let new_default_init_error = Database::new_default_initialized().await;
+        let create_default_error = create_default_database().await;
+
+        // All should return PathResolution errors
+        assert!(matches!(new_default_error, Err(DatabaseError::PathResolution(_))));
+        assert!(matches!(new_default_init_error, Err(DatabaseError::PathResolution(_))));
+        assert!(matches!(create_default_error, Err(DatabaseError::PathResolution(_))));
+
+        // Test mode resolves under the temp dir, so it does not need USERPROFILE
+        set_test_mode(true);
+        assert!(Database::new_default().is_ok());
+        set_test_mode(false);
+
+        if let Some(original) = original_userprofile {
+            std::env::set_var("USERPROFILE", original);
+        }
+    }
+
+    set_test_mode(true);
+    assert!(is_test_mode());
+
+    let test_path = get_default_database_path().expect("Should resolve test path");
+    println!("Test mode database path: {}", test_path.display());
+    assert!(test_path.starts_with(std::env::temp_dir()), "Test path should be under the temp dir");
+    assert!(test_path.to_string_lossy().contains(&std::process::id().to_string()));
+    assert!(test_path.ends_with("data.db"));
+    assert_ne!(test_path, real_path);
+
+    // Creating the default database only ever touches the temp location
+    if let Ok(db) = create_default_database().await {
+        let _ = db.close().await;
+    }
+    assert!(test_path.parent().unwrap().exists(), "Directory should be created under the temp dir");
+    let _ = std::fs::remove_dir_all(test_path.parent().unwrap());
+
+    set_test_mode(false);
+    assert_eq!(get_default_database_path().unwrap(), real_path);
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "tracing")]
+
+use burncloud_database_core::create_in_memory_database;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+// Tracing tests
+// These tests cover the query spans emitted with the `tracing` feature
+
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CapturedLogs {
+    // sqlx's worker thread holds a handle to the span while it runs the
+    // statement, so the close line can land just after the query returns.
+    async fn wait_for(&self, needle: &str) -> String {
+        for _ in 0..100 {
+            let output = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+            if output.contains(needle) {
+                return output;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("{:?} was never logged", needle);
+    }
+}
+
+#[tokio::test]
+async fn test_queries_emit_spans() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_query("CREATE TABLE models (name TEXT)").await.unwrap();
+
+    db.execute_query("INSERT INTO models VALUES ('a'), ('b')").await.unwrap();
+    let output = logs.wait_for("query{sql=\"INSERT INTO models VALUES ('a'), ('b')\"").await;
+    let line = output.lines().find(|line| line.contains("INSERT INTO models")).unwrap();
+    assert!(line.contains("duration_ms=") && line.contains("rows=2"), "{}", line);
+
+    let names: Vec<(String,)> = db.fetch_all("SELECT name FROM models").await.unwrap();
+    assert_eq!(names.len(), 2);
+    let output = logs.wait_for("query{sql=\"SELECT name FROM models\"").await;
+    let line = output.lines().find(|line| line.contains("SELECT name FROM models")).unwrap();
+    assert!(line.contains("rows=2"), "{}", line);
+
+    assert!(db.fetch_one::<(String,)>("SELECT nope FROM models").await.is_err());
+    let output = logs.wait_for("query failed").await;
+    let line = output.lines().find(|line| line.contains("query failed")).unwrap();
+    assert!(line.contains("WARN") && line.contains("no such column"), "{}", line);
+
+    let long_query = format!("SELECT 1 AS {}", "x".repeat(500));
+    let _: Option<(i64,)> = db.fetch_optional(&long_query).await.unwrap();
+    let output = logs.wait_for(&format!("\"{}...\"", &long_query[..200])).await;
+    assert!(!output.contains(&long_query));
+}
@@ -0,0 +1,266 @@
+use burncloud_database_core::{create_database, Database, DatabaseError, DatabaseTransaction, Result};
+use std::time::Duration;
+use tempfile::TempDir;
+
+// Transaction tests
+// These tests cover explicit transactions opened against file databases
+
+async fn create_file_database(temp_dir: &TempDir) -> Database {
+    let db_path = temp_dir.path().join("transactions.db");
+    std::fs::File::create(&db_path).expect("Should create database file");
+
+    let db = create_database(&db_path).await.expect("Should create database");
+    db.execute_query("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+        .await
+        .expect("Should create table");
+    db
+}
+
+async fn count_items(db: &Database) -> i64 {
+    let (count,): (i64,) = db
+        .fetch_one("SELECT COUNT(*) FROM items")
+        .await
+        .expect("Should count rows");
+    count
+}
+
+#[tokio::test]
+async fn test_transaction_timeout_rolls_back() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_file_database(&temp_dir).await;
+
+    let mut tx = db
+        .begin_with_timeout(Duration::from_millis(100))
+        .await
+        .expect("Should begin transaction");
+    tx.execute_query("INSERT INTO items (name) VALUES ('pending')")
+        .await
+        .expect("Insert within the window should succeed");
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    let late_insert = tx.execute_query("INSERT INTO items (name) VALUES ('late')").await;
+    assert!(matches!(late_insert, Err(DatabaseError::Timeout)));
+
+    let commit_result = tx.commit().await;
+    assert!(matches!(commit_result, Err(DatabaseError::Timeout)));
+
+    assert_eq!(count_items(&db).await, 0, "Timed out transaction should be rolled back");
+
+    // The write lock must have been released by the rollback
+    db.execute_query("INSERT INTO items (name) VALUES ('after')")
+        .await
+        .expect("Database should accept writes after the timeout");
+    assert_eq!(count_items(&db).await, 1);
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_transaction_within_timeout_commits() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_file_database(&temp_dir).await;
+
+    let mut tx = db
+        .begin_with_timeout(Duration::from_secs(5))
+        .await
+        .expect("Should begin transaction");
+    tx.execute_query("INSERT INTO items (name) VALUES ('first')")
+        .await
+        .expect("Should insert");
+    tx.execute_query("INSERT INTO items (name) VALUES ('second')")
+        .await
+        .expect("Should insert");
+
+    let (in_tx,): (i64,) = tx
+        .fetch_one("SELECT COUNT(*) FROM items")
+        .await
+        .expect("Should read inside the transaction");
+    assert_eq!(in_tx, 2);
+
+    tx.commit().await.expect("Commit within the window should succeed");
+    assert_eq!(count_items(&db).await, 2);
+
+    let _ = db.close().await;
+}
+
+#[tokio::test]
+async fn test_transaction_rollback_discards_rows() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_file_database(&temp_dir).await;
+
+    let mut tx = db.begin().await.expect("Should begin transaction");
+    tx.execute_query("INSERT INTO items (name) VALUES ('first')").await.unwrap();
+    tx.execute_query("INSERT INTO items (name) VALUES ('second')").await.unwrap();
+    let (inside,): (i64,) = tx.fetch_one("SELECT COUNT(*) FROM items").await.unwrap();
+    assert_eq!(inside, 2, "Rows should be visible inside the transaction");
+    tx.rollback().await.expect("Rollback should succeed");
+
+    assert_eq!(count_items(&db).await, 0);
+
+    // Dropping an uncommitted transaction rolls it back too
+    let mut tx = db.begin().await.unwrap();
+    tx.execute_query("INSERT INTO items (name) VALUES ('dropped')").await.unwrap();
+    drop(tx);
+    assert_eq!(count_items(&db).await, 0);
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_transaction_commit_persists_rows() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_file_database(&temp_dir).await;
+
+    let mut tx = db.begin().await.expect("Should begin transaction");
+    tx.execute_query("INSERT INTO items (name) VALUES ('first')").await.unwrap();
+    tx.execute_query("INSERT INTO items (name) VALUES ('second')").await.unwrap();
+    tx.commit().await.expect("Commit should succeed");
+
+    let names: Vec<(String,)> = db.fetch_all("SELECT name FROM items ORDER BY id").await.unwrap();
+    assert_eq!(names, vec![("first".to_string(),), ("second".to_string(),)]);
+
+    db.close().await.unwrap();
+}
+
+async fn insert_item(tx: &mut DatabaseTransaction, name: &str) -> Result<()> {
+    tx.execute_query(&format!("INSERT INTO items (name) VALUES ('{}')", name))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transaction_closure_commits_on_ok() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_file_database(&temp_dir).await;
+
+    let inserted = db
+        .transaction(|tx| {
+            Box::pin(async move {
+                insert_item(tx, "first").await?;
+                insert_item(tx, "second").await?;
+                let (count,): (i64,) = tx.fetch_one("SELECT COUNT(*) FROM items").await?;
+                Ok(count)
+            })
+        })
+        .await
+        .expect("Transaction should commit");
+
+    assert_eq!(inserted, 2);
+    assert_eq!(count_items(&db).await, 2);
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_transaction_closure_rolls_back_and_propagates_error() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_file_database(&temp_dir).await;
+
+    let result: Result<()> = db
+        .transaction(|tx| {
+            Box::pin(async move {
+                insert_item(tx, "doomed").await?;
+                Err(DatabaseError::Query("validation failed".to_string()))
+            })
+        })
+        .await;
+
+    match result {
+        Err(DatabaseError::Query(message)) => assert_eq!(message, "validation failed"),
+        other => panic!("Expected the closure's error unchanged, got {:?}", other),
+    }
+    assert_eq!(count_items(&db).await, 0);
+
+    // A failing statement part-way through a nested helper rolls back earlier writes too
+    let result: Result<()> = db
+        .transaction(|tx| {
+            Box::pin(async move {
+                insert_item(tx, "kept?").await?;
+                tx.execute_query("INSERT INTO missing_table VALUES (1)").await?;
+                Ok(())
+            })
+        })
+        .await;
+    assert!(matches!(result, Err(DatabaseError::Connection(_))));
+    assert_eq!(count_items(&db).await, 0);
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_transaction_closure_rolls_back_on_panic() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_file_database(&temp_dir).await;
+    let db_path = temp_dir.path().join("transactions.db");
+
+    let handle = tokio::spawn(async move {
+        db.transaction(|tx| {
+            Box::pin(async move {
+                insert_item(tx, "panicking").await?;
+                panic!("closure panicked");
+                #[allow(unreachable_code)]
+                Ok(())
+            })
+        })
+        .await
+    });
+    assert!(handle.await.is_err(), "The panic should surface as a join error");
+
+    let db = create_database(&db_path).await.unwrap();
+    assert_eq!(count_items(&db).await, 0);
+    db.close().await.unwrap();
+}
+
+async fn item_names(db: &Database) -> Vec<String> {
+    let rows: Vec<(String,)> = db.fetch_all("SELECT name FROM items ORDER BY id").await.unwrap();
+    rows.into_iter().map(|(name,)| name).collect()
+}
+
+#[tokio::test]
+async fn test_savepoint_rollback_keeps_outer_work() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_file_database(&temp_dir).await;
+
+    let mut tx = db.begin().await.unwrap();
+    insert_item(&mut tx, "outer").await.unwrap();
+
+    let mut savepoint = tx.savepoint("import_batch").await.expect("Should create savepoint");
+    savepoint.execute_query("INSERT INTO items (name) VALUES ('inner')").await.unwrap();
+    let (inside,): (i64,) = savepoint.fetch_one("SELECT COUNT(*) FROM items").await.unwrap();
+    assert_eq!(inside, 2);
+    savepoint.rollback().await.expect("Should roll back to the savepoint");
+
+    let mut released = tx.savepoint("released").await.unwrap();
+    released.execute_query("INSERT INTO items (name) VALUES ('released')").await.unwrap();
+    released.commit().await.expect("Should release the savepoint");
+
+    tx.commit().await.unwrap();
+    assert_eq!(item_names(&db).await, vec!["outer", "released"]);
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_dropped_savepoint_rolls_back_and_names_are_validated() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_file_database(&temp_dir).await;
+
+    let mut tx = db.begin().await.unwrap();
+    insert_item(&mut tx, "outer").await.unwrap();
+    {
+        let mut outer = tx.savepoint("outer_sp").await.unwrap();
+        outer.execute_query("INSERT INTO items (name) VALUES ('abandoned')").await.unwrap();
+        let mut nested = outer.savepoint("nested_sp").await.unwrap();
+        nested.execute_query("INSERT INTO items (name) VALUES ('nested')").await.unwrap();
+        // Both guards are dropped here without commit
+    }
+
+    let invalid = tx.savepoint("sp; DROP TABLE items").await.err();
+    assert!(matches!(invalid, Some(DatabaseError::InvalidData { .. })));
+
+    insert_item(&mut tx, "after").await.unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!(item_names(&db).await, vec!["outer", "after"]);
+
+    db.close().await.unwrap();
+}
@@ -0,0 +1,72 @@
+use burncloud_database_core::{create_database, create_in_memory_database, DatabaseError, SqlValue};
+use tempfile::TempDir;
+
+// Writer queue tests
+// These tests cover fire-and-forget inserts through Database::writer_queue
+
+const CREATE_LOGS: &str = "CREATE TABLE request_logs (id INTEGER PRIMARY KEY, path TEXT NOT NULL, status INTEGER)";
+
+#[tokio::test]
+async fn test_enqueued_inserts_land_after_flush() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_database(temp_dir.path().join("logs.db")).await.unwrap();
+    db.execute_query(CREATE_LOGS).await.unwrap();
+
+    let queue = db.writer_queue(2000).unwrap();
+    for i in 0..1500 {
+        queue
+            .enqueue(
+                "INSERT INTO request_logs (path, status) VALUES (?, ?)",
+                vec![SqlValue::Text(format!("/v1/models/{}", i)), SqlValue::Integer(200)],
+            )
+            .unwrap();
+    }
+    queue.flush().await.unwrap();
+
+    let (count,): (i64,) = db.fetch_one("SELECT COUNT(*) FROM request_logs").await.unwrap();
+    assert_eq!(count, 1500);
+    queue.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_flush_reports_failed_statements_and_keeps_the_rest() {
+    let db = create_in_memory_database().await.unwrap();
+    db.execute_query(CREATE_LOGS).await.unwrap();
+
+    let queue = db.writer_queue(16).unwrap();
+    let insert = "INSERT INTO request_logs (path, status) VALUES (?, ?)";
+    queue.enqueue(insert, vec![SqlValue::Text("/ok".into()), SqlValue::Integer(200)]).unwrap();
+    queue.enqueue(insert, vec![SqlValue::Null, SqlValue::Integer(500)]).unwrap();
+    queue.enqueue(insert, vec![SqlValue::Text("/also-ok".into()), SqlValue::Integer(201)]).unwrap();
+
+    assert!(matches!(queue.flush().await, Err(DatabaseError::ConstraintViolation { .. })));
+    // The error is reported once
+    queue.flush().await.unwrap();
+
+    let (count,): (i64,) = db.fetch_one("SELECT COUNT(*) FROM request_logs").await.unwrap();
+    assert_eq!(count, 2);
+}
+
+#[tokio::test]
+async fn test_shutdown_drains_queue() {
+    let temp_dir = TempDir::new().expect("Should create temp directory");
+    let db = create_database(temp_dir.path().join("logs.db")).await.unwrap();
+    db.execute_query(CREATE_LOGS).await.unwrap();
+
+    let queue = db.writer_queue(100).unwrap();
+    for _ in 0..50 {
+        queue
+            .enqueue("INSERT INTO request_logs (path) VALUES ('/drain')", vec![])
+            .unwrap();
+    }
+    queue.shutdown().await.unwrap();
+
+    let (count,): (i64,) = db.fetch_one("SELECT COUNT(*) FROM request_logs").await.unwrap();
+    assert_eq!(count, 50);
+}
+
+#[tokio::test]
+async fn test_writer_queue_rejects_zero_capacity() {
+    let db = create_in_memory_database().await.unwrap();
+    assert!(matches!(db.writer_queue(0), Err(DatabaseError::InvalidData { .. })));
+}
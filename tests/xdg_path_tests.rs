@@ -0,0 +1,34 @@
+#![cfg(all(unix, not(target_os = "macos")))]
+
+use burncloud_database_core::{get_default_database_path, set_legacy_default_path};
+use tempfile::TempDir;
+
+// XDG default path tests
+// These change XDG_DATA_HOME for the whole process, so they run in their own test binary
+
+#[test]
+fn test_default_path_follows_xdg_data_home() {
+    let data_home = TempDir::new().expect("Should create temp directory");
+
+    std::env::set_var("XDG_DATA_HOME", data_home.path());
+    let path = get_default_database_path().expect("Should resolve default path");
+    assert_eq!(path, data_home.path().join("burncloud").join("data.db"));
+
+    // Empty or relative values are treated as unset
+    let fallback = dirs::home_dir().unwrap().join(".local/share/burncloud/data.db");
+    std::env::set_var("XDG_DATA_HOME", "");
+    assert_eq!(get_default_database_path().unwrap(), fallback);
+    std::env::set_var("XDG_DATA_HOME", "relative/dir");
+    assert_eq!(get_default_database_path().unwrap(), fallback);
+
+    // The legacy layout ignores XDG_DATA_HOME entirely
+    std::env::set_var("XDG_DATA_HOME", data_home.path());
+    set_legacy_default_path(true);
+    assert_eq!(
+        get_default_database_path().unwrap(),
+        dirs::home_dir().unwrap().join(".burncloud").join("data.db")
+    );
+    set_legacy_default_path(false);
+
+    std::env::remove_var("XDG_DATA_HOME");
+}